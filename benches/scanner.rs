@@ -0,0 +1,84 @@
+// Compares scanning borrowed tokens with converting every token to an owned one, which is
+// what the scanner used to do. Run with `cargo bench --bench scanner`
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use lox::token_scanner::Scanner;
+
+const INPUT_SIZE: usize = 10 * 1024 * 1024;
+const RUNS: usize = 5;
+
+// Counts heap allocations so that the allocations per token can be reported
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn input() -> String {
+    let snippet = "class Point < Base {\n  init(x, y) { this.x = x; this.y = y; }\n  norm() { return clock() * (this.x + this.y) / 2.5; }\n}\n// A line comment\nvar message = \"hello, world\";\nfor (var i = 0; i < 100; i = i + 1) { print message; }\n";
+    snippet.repeat(INPUT_SIZE / snippet.len() + 1)
+}
+
+// Best wall time of all runs, along with the tokens and allocations of the last one
+fn measure(source: &str, scan: impl Fn(&str) -> usize) -> (Duration, usize, usize) {
+    let mut best = Duration::MAX;
+    let mut tokens = 0;
+    let mut allocations = 0;
+    for _ in 0..RUNS {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        tokens = black_box(scan(source));
+        best = best.min(start.elapsed());
+        allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    }
+
+    (best, tokens, allocations)
+}
+
+fn report(name: &str, (time, tokens, allocations): (Duration, usize, usize), size: usize) {
+    let throughput = size as f64 / time.as_secs_f64() / (1024.0 * 1024.0);
+    println!(
+        "{name:<10} {time:>12.2?} {throughput:>10.1} MB/s {tokens:>10} tokens {:>8.3} allocations/token",
+        allocations as f64 / tokens as f64
+    );
+}
+
+fn main() {
+    let source = input();
+    println!("Scanning {} bytes, best of {RUNS} runs", source.len());
+
+    let borrowed = measure(&source, |source| {
+        let mut count = 0;
+        for token in Scanner::new(source) {
+            black_box(token.unwrap());
+            count += 1;
+        }
+        count
+    });
+    report("borrowed", borrowed, source.len());
+
+    let owned = measure(&source, |source| {
+        let mut count = 0;
+        for token in Scanner::new(source) {
+            black_box(token.unwrap().into_owned());
+            count += 1;
+        }
+        count
+    });
+    report("owned", owned, source.len());
+}
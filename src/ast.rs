@@ -1,7 +1,6 @@
 use crate::expr::Expr;
-use crate::tokens::Literal;
 
-struct AstPrinter {}
+pub struct AstPrinter {}
 
 impl AstPrinter {
     pub fn print(e: Expr) -> String {
@@ -13,19 +12,66 @@ impl AstPrinter {
             } => {
                 let formatted_left = AstPrinter::print(*left).to_owned();
                 let formatted_right = AstPrinter::print(*right).to_owned();
-                let operator_lexeme = operator.lexeme.to_owned();
+                let operator_lexeme = operator.lexeme.to_string();
                 format!("({operator_lexeme} {formatted_left} {formatted_right})")
             }
             Expr::Unary { operator, right } => {
-                let operator_lexeme = operator.lexeme.to_owned();
+                let operator_lexeme = operator.lexeme.to_string();
                 let formatted_expr = AstPrinter::print(*right).to_owned();
                 format!("({operator_lexeme} {formatted_expr})")
             }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                let mut formatted = format!("(call {}", AstPrinter::print(*callee));
+                for argument in arguments {
+                    formatted.push(' ');
+                    formatted.push_str(&AstPrinter::print(argument));
+                }
+                formatted.push(')');
+                formatted
+            }
+            Expr::Get { object, name } => {
+                let formatted_object = AstPrinter::print(*object).to_owned();
+                let name_lexeme = name.lexeme.to_string();
+                format!("(. {formatted_object} {name_lexeme})")
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => {
+                let formatted_object = AstPrinter::print(*object).to_owned();
+                let name_lexeme = name.lexeme.to_string();
+                let formatted_value = AstPrinter::print(*value).to_owned();
+                format!("(= (. {formatted_object} {name_lexeme}) {formatted_value})")
+            }
+            Expr::This { .. } => String::from("this"),
+            Expr::Super { method, .. } => {
+                let method_lexeme = method.lexeme.to_string();
+                format!("(. super {method_lexeme})")
+            }
             Expr::Grouping { expr } => {
                 let formatted_expr = AstPrinter::print(*expr).to_owned();
                 format!("(group {formatted_expr})")
             }
             Expr::Literal { value } => value.to_str(),
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let formatted_left = AstPrinter::print(*left).to_owned();
+                let formatted_right = AstPrinter::print(*right).to_owned();
+                let operator_lexeme = operator.lexeme.to_string();
+                format!("({operator_lexeme} {formatted_left} {formatted_right})")
+            }
+            Expr::Variable { name, .. } => name.lexeme.into_owned(),
+            Expr::Assign { name, value, .. } => {
+                let name_lexeme = name.lexeme.to_string();
+                let formatted_value = AstPrinter::print(*value).to_owned();
+                format!("(= {name_lexeme} {formatted_value})")
+            }
         }
     }
 }
@@ -34,6 +80,7 @@ impl AstPrinter {
 mod tests {
     use crate::{
         expr::Expr,
+        source_map::Span,
         tokens::{Literal, Token, TokenType},
     };
 
@@ -43,26 +90,12 @@ mod tests {
     fn test_print() {
         let expr = Expr::Binary {
             left: Box::new(Expr::Unary {
-                operator: Token {
-                    token_type: TokenType::Minus,
-                    lexeme: String::from("-"),
-                    line: 1,
-                    position_start: 0,
-                    position_end: 0,
-                    literal: None,
-                },
+                operator: Token::new(TokenType::Minus, "-", 1, Span::default(), None),
                 right: Box::new(Expr::Literal {
                     value: Literal::Number { value: 123.0 },
                 }),
             }),
-            operator: Token {
-                token_type: TokenType::Star,
-                lexeme: String::from("*"),
-                line: 1,
-                position_start: 0,
-                position_end: 0,
-                literal: None,
-            },
+            operator: Token::new(TokenType::Star, "*", 1, Span::default(), None),
             right: {
                 Box::new(Expr::Grouping {
                     expr: Box::new(Expr::Literal {
@@ -0,0 +1,110 @@
+use crate::error_codes::ErrorCode;
+use crate::errors::RuntimeError;
+use crate::function::LoxFunction;
+use crate::tokens::Token;
+use crate::utils::suggestion;
+use crate::value::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+pub struct LoxClass {
+    pub name: String,
+    pub superclass: Option<Rc<LoxClass>>,
+    methods: HashMap<String, Rc<LoxFunction>>,
+}
+
+impl LoxClass {
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<LoxClass>>,
+        methods: HashMap<String, Rc<LoxFunction>>,
+    ) -> Self {
+        LoxClass {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    // Methods are looked up on the class first, then up the inheritance chain
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self
+                .superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name)),
+        }
+    }
+
+    // Names of the methods of the class and of its superclasses
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.methods.keys().cloned().collect();
+        if let Some(superclass) = &self.superclass {
+            names.extend(superclass.method_names());
+        }
+
+        names
+    }
+
+    pub fn arity(&self) -> usize {
+        self.find_method("init")
+            .map(|initializer| initializer.arity())
+            .unwrap_or(0)
+    }
+}
+
+impl fmt::Debug for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoxClass")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct LoxInstance {
+    pub class: Rc<LoxClass>,
+    fields: HashMap<String, Value>,
+}
+
+impl LoxInstance {
+    pub fn new(class: Rc<LoxClass>) -> Self {
+        LoxInstance {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    // Fields shadow methods with the same name
+    pub fn get(instance: &Rc<RefCell<LoxInstance>>, name: &Token) -> Result<Value, RuntimeError> {
+        if let Some(value) = instance.borrow().fields.get(name.lexeme.as_ref()) {
+            return Ok(value.clone());
+        }
+
+        let method = instance.borrow().class.find_method(name.lexeme.as_ref());
+        match method {
+            Some(method) => Ok(Value::Function(Rc::new(
+                method.bind(Value::Instance(Rc::clone(instance))),
+            ))),
+            None => {
+                let error = RuntimeError::new(
+                    ErrorCode::UndefinedProperty,
+                    format!("Undefined property '{}'", name.lexeme),
+                    name,
+                );
+                let instance = instance.borrow();
+                let mut names: Vec<&str> = instance.fields.keys().map(String::as_str).collect();
+                let methods = instance.class.method_names();
+                names.extend(methods.iter().map(String::as_str));
+                Err(error.with_help(suggestion(&name.lexeme, names)))
+            }
+        }
+    }
+
+    pub fn set(&mut self, name: &Token, value: Value) {
+        self.fields.insert(name.lexeme.to_string(), value);
+    }
+}
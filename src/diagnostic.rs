@@ -0,0 +1,476 @@
+use crate::error_codes::{ErrorCode, Severity};
+use crate::source_map::{SourceFile, SourceMap, Span};
+
+// Secondary span of a diagnostic, such as where an unterminated string started
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+// Error ready to be shown to the user with the source it points at
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<ErrorCode>,
+    pub message: String,
+    pub span: Span,
+    pub labels: Vec<Label>,
+    // Suggestion shown after the source, such as a similar name
+    pub help: Option<String>,
+}
+
+// Underline of a span, on the line where the span starts
+struct Mark<'a> {
+    line: usize,
+    column: usize,
+    width: usize,
+    primary: bool,
+    message: &'a str,
+}
+
+const RED: &str = "1;31";
+const YELLOW: &str = "1;33";
+const BLUE: &str = "1;34";
+const BOLD: &str = "1";
+
+impl Diagnostic {
+    pub fn new(message: String, span: Span, labels: Vec<Label>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code: None,
+            message,
+            span,
+            labels,
+            help: None,
+        }
+    }
+
+    // A missing help keeps the current one
+    pub fn with_help(mut self, help: Option<String>) -> Self {
+        self.help = help.or(self.help);
+        self
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.severity = code.severity();
+        self.code = Some(code);
+        self
+    }
+
+    // Header with the message, location of the span, then every source line that a span
+    // starts on, with `^` under the primary span and `-` under the labels
+    pub fn render(&self, source_map: &SourceMap, color: bool) -> String {
+        let paint = |code: &str, text: &str| {
+            if color {
+                format!("\x1b[{code}m{text}\x1b[0m")
+            } else {
+                String::from(text)
+            }
+        };
+
+        let severity_color = match self.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        };
+        let header = match self.code {
+            Some(code) => format!("{}[{code}]", self.severity),
+            None => self.severity.to_string(),
+        };
+        let mut rendered = format!(
+            "{}{}\n",
+            paint(severity_color, &header),
+            paint(BOLD, &format!(": {}", self.message))
+        );
+        let help = |indent: &str| match &self.help {
+            Some(help) => format!(
+                "{indent} {} {}: {help}\n",
+                paint(BLUE, "="),
+                paint(BOLD, "help")
+            ),
+            None => String::new(),
+        };
+        let Some(file) = source_map.file(self.span.file_id) else {
+            rendered.push_str(&help(""));
+            return rendered;
+        };
+
+        let mut marks = vec![mark(file, self.span, true, "")];
+        marks.extend(
+            self.labels
+                .iter()
+                .map(|label| mark(file, label.span, false, &label.message)),
+        );
+        marks.sort_by_key(|m| m.line);
+
+        let (line, column) = file.line_col(self.span.start);
+        let gutter = marks
+            .iter()
+            .map(|m| m.line)
+            .max()
+            .unwrap_or(line)
+            .to_string()
+            .len();
+        let empty = " ".repeat(gutter);
+        rendered.push_str(&format!(
+            "{}{} {}:{line}:{column}\n",
+            empty,
+            paint(BLUE, "-->"),
+            file.name
+        ));
+        rendered.push_str(&format!("{empty} {}\n", paint(BLUE, "|")));
+
+        let mut previous_line = None;
+        for m in &marks {
+            let line_text = file.line_text(m.line).unwrap_or_default();
+            if previous_line != Some(m.line) {
+                if previous_line.is_some_and(|previous| m.line > previous + 1) {
+                    rendered.push_str(&format!("{}\n", paint(BLUE, "...")));
+                }
+                rendered.push_str(&format!(
+                    "{} {line_text}\n",
+                    paint(BLUE, &format!("{:>gutter$} |", m.line))
+                ));
+                previous_line = Some(m.line);
+            }
+
+            // Tabs are kept so that the underline stays aligned with the text
+            let padding: String = line_text
+                .chars()
+                .take(m.column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let (marker, code) = if m.primary {
+                ('^', severity_color)
+            } else {
+                ('-', BLUE)
+            };
+            let underline = marker.to_string().repeat(m.width);
+            let text = if m.message.is_empty() {
+                underline
+            } else {
+                format!("{underline} {}", m.message)
+            };
+            rendered.push_str(&format!(
+                "{empty} {} {padding}{}\n",
+                paint(BLUE, "|"),
+                paint(code, &text)
+            ));
+        }
+        rendered.push_str(&help(&empty));
+
+        rendered
+    }
+
+    // Single line JSON object, for tools that read one diagnostic per line
+    pub fn to_json(&self, source_map: &SourceMap) -> String {
+        let code = match self.code {
+            Some(code) => json_string(code.code()),
+            None => String::from("null"),
+        };
+        let help = match &self.help {
+            Some(help) => json_string(help),
+            None => String::from("null"),
+        };
+        let labels: Vec<String> = self
+            .labels
+            .iter()
+            .map(|label| {
+                format!(
+                    "{{\"message\":{}{}}}",
+                    json_string(&label.message),
+                    json_location(source_map, label.span)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"code\":{code},\"severity\":{},\"message\":{}{},\"labels\":[{}],\"help\":{help}}}",
+            json_string(&self.severity.to_string()),
+            json_string(&self.message),
+            json_location(source_map, self.span),
+            labels.join(",")
+        )
+    }
+}
+
+// SARIF 2.1.0 log with one result per diagnostic, and one rule per error code used
+pub fn sarif(diagnostics: &[Diagnostic], source_map: &SourceMap) -> String {
+    let mut codes: Vec<ErrorCode> = vec![];
+    for code in diagnostics.iter().filter_map(|d| d.code) {
+        if !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+    let rules: Vec<String> = codes
+        .iter()
+        .map(|code| {
+            let explanation = code.explanation();
+            let summary = explanation.split("\n\n").next().unwrap_or_default();
+            format!(
+                "{{\"id\":{},\"shortDescription\":{{\"text\":{}}},\"fullDescription\":{{\"text\":{}}}}}",
+                json_string(code.code()),
+                json_string(&summary.replace('\n', " ")),
+                json_string(explanation)
+            )
+        })
+        .collect();
+    let results: Vec<String> = diagnostics
+        .iter()
+        .map(|d| {
+            let rule = match d.code {
+                Some(code) => format!("\"ruleId\":{},", json_string(code.code())),
+                None => String::new(),
+            };
+            // Dashboards only show the message, so the help is appended to it
+            let message = match &d.help {
+                Some(help) => format!("{}\nhelp: {help}", d.message),
+                None => d.message.clone(),
+            };
+            let related: Vec<String> = d
+                .labels
+                .iter()
+                .enumerate()
+                .map(|(id, label)| {
+                    let location = match sarif_location(source_map, label.span) {
+                        Some(location) => format!(",{location}"),
+                        None => String::new(),
+                    };
+                    format!(
+                        "{{\"id\":{id},\"message\":{{\"text\":{}}}{location}}}",
+                        json_string(&label.message)
+                    )
+                })
+                .collect();
+            let location = match sarif_location(source_map, d.span) {
+                Some(location) => format!("{{{location}}}"),
+                None => String::new(),
+            };
+            format!(
+                "{{{rule}\"level\":{},\"message\":{{\"text\":{}}},\"locations\":[{location}],\"relatedLocations\":[{}]}}",
+                json_string(&d.severity.to_string()),
+                json_string(&message),
+                related.join(",")
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\",\"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"lox\",\"rules\":[{}]}}}},\"columnKind\":\"unicodeCodePoints\",\"results\":[{}]}}]}}",
+        rules.join(","),
+        results.join(",")
+    )
+}
+
+// 1-based line and character column
+type Position = (usize, usize);
+
+// Same positions as the rendered diagnostics, the end is exclusive. Spans of unknown
+// files have no position
+fn span_position(source_map: &SourceMap, span: Span) -> Option<(&str, Position, Position)> {
+    let file = source_map.file(span.file_id)?;
+    Some((
+        &file.name,
+        file.line_col(span.start),
+        file.line_col(span.end),
+    ))
+}
+
+// Location keys with a leading comma, or nothing when the position is unknown
+fn json_location(source_map: &SourceMap, span: Span) -> String {
+    match span_position(source_map, span) {
+        Some((file, (line, column), (end_line, end_column))) => format!(
+            ",\"file\":{},\"line\":{line},\"column\":{column},\"end_line\":{end_line},\"end_column\":{end_column}",
+            json_string(file)
+        ),
+        None => String::new(),
+    }
+}
+
+// Physical location of a span. Pseudo-files such as `<stdin>` and `<repl>` have no
+// artifact to point at, so only their region is given
+fn sarif_location(source_map: &SourceMap, span: Span) -> Option<String> {
+    let (file, (line, column), (end_line, end_column)) = span_position(source_map, span)?;
+    let artifact = if file.starts_with('<') && file.ends_with('>') {
+        String::new()
+    } else {
+        format!(
+            "\"artifactLocation\":{{\"uri\":{}}},",
+            json_string(&uri_reference(file))
+        )
+    };
+    Some(format!(
+        "\"physicalLocation\":{{{artifact}\"region\":{{\"startLine\":{line},\"startColumn\":{column},\"endLine\":{end_line},\"endColumn\":{end_column}}}}}"
+    ))
+}
+
+// Path as a URI reference: bytes other than unreserved characters and `/` are
+// percent-encoded
+fn uri_reference(path: &str) -> String {
+    let mut uri = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    uri
+}
+
+// Quoted JSON string with the characters that JSON requires to be escaped
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
+
+fn mark<'a>(file: &SourceFile, span: Span, primary: bool, message: &'a str) -> Mark<'a> {
+    let (line, column) = file.line_col(span.start);
+    let (end_line, end_column) = file.line_col(span.end);
+    let width = if end_line == line {
+        end_column - column
+    } else {
+        // Multi line spans are underlined up to the end of their first line
+        let line_length = file.line_text(line).unwrap_or_default().chars().count();
+        (line_length + 1).saturating_sub(column)
+    };
+
+    Mark {
+        line,
+        column,
+        width: width.max(1),
+        primary,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sarif, Diagnostic, Label};
+    use crate::error_codes::ErrorCode;
+    use crate::source_map::{SourceMap, Span};
+
+    #[test]
+    fn test_render() {
+        let mut source_map = SourceMap::new();
+        let id = source_map.add_file(
+            String::from("test.lox"),
+            String::from("var é = 1;\n\tprint é + @@;\n\n\nprint \"abc\n"),
+        );
+
+        let diagnostic = Diagnostic::new(
+            String::from("Invalid character"),
+            Span::new(24, 26, id),
+            vec![],
+        );
+        assert_eq!(
+            diagnostic.render(&source_map, false),
+            "error: Invalid character\n --> test.lox:2:12\n  |\n2 | \tprint é + @@;\n  | \t          ^^\n"
+        );
+
+        let diagnostic = Diagnostic::new(
+            String::from("Non terminated string"),
+            Span::new(36, 37, id),
+            vec![
+                Label {
+                    span: Span::new(4, 6, id),
+                    message: String::from("unrelated"),
+                },
+                Label {
+                    span: Span::new(41, 41, id),
+                    message: String::from("the file ends here"),
+                },
+            ],
+        );
+        assert_eq!(
+            diagnostic.render(&source_map, false),
+            "error: Non terminated string\n --> test.lox:5:7\n  |\n1 | var é = 1;\n  |     - unrelated\n...\n5 | print \"abc\n  |       ^\n6 | \n  | - the file ends here\n"
+        );
+
+        let colored = diagnostic.render(&source_map, true);
+        assert!(colored.starts_with("\x1b[1;31merror\x1b[0m"));
+        assert!(colored.contains("\x1b[1;31m^\x1b[0m"));
+
+        let coded = diagnostic.with_code(ErrorCode::UnterminatedString);
+        assert!(coded
+            .render(&source_map, false)
+            .starts_with("error[E0101]: Non terminated string\n"));
+    }
+
+    #[test]
+    fn test_json_and_sarif() {
+        let mut source_map = SourceMap::new();
+        let id = source_map.add_file(String::from("dir/\"q\".lox"), String::from("é = \"a\n"));
+        let diagnostic = Diagnostic::new(
+            String::from("Non terminated string"),
+            Span::new(5, 6, id),
+            vec![Label {
+                span: Span::new(8, 8, id),
+                message: String::from("the file ends before the closing '\"'"),
+            }],
+        )
+        .with_code(ErrorCode::UnterminatedString);
+
+        assert_eq!(
+            diagnostic.to_json(&source_map),
+            "{\"code\":\"E0101\",\"severity\":\"error\",\"message\":\"Non terminated string\",\"file\":\"dir/\\\"q\\\".lox\",\"line\":1,\"column\":5,\"end_line\":1,\"end_column\":6,\"labels\":[{\"message\":\"the file ends before the closing '\\\"'\",\"file\":\"dir/\\\"q\\\".lox\",\"line\":2,\"column\":1,\"end_line\":2,\"end_column\":1}],\"help\":null}"
+        );
+
+        let log = sarif(&[diagnostic.clone(), diagnostic], &source_map);
+        assert!(log.starts_with("{\"$schema\":"));
+        assert_eq!(log.matches("{\"id\":\"E0101\"").count(), 1);
+        assert_eq!(
+            log.matches("\"ruleId\":\"E0101\",\"level\":\"error\"")
+                .count(),
+            2
+        );
+        assert!(log.contains(
+            "\"artifactLocation\":{\"uri\":\"dir/%22q%22.lox\"},\"region\":{\"startLine\":1,\"startColumn\":5,\"endLine\":1,\"endColumn\":6}"
+        ));
+
+        // Pseudo-files have no artifact, unknown files no location at all
+        let stdin = source_map.add_file(String::from("<stdin>"), String::from("@"));
+        let diagnostic = Diagnostic::new(
+            String::from("Invalid character"),
+            Span::new(0, 1, stdin),
+            vec![Label {
+                span: Span::new(0, 1, source_map.next_file_id()),
+                message: String::from("elsewhere"),
+            }],
+        );
+        assert_eq!(
+            sarif(&[diagnostic], &source_map).split("\"results\":").nth(1),
+            Some("[{\"level\":\"error\",\"message\":{\"text\":\"Invalid character\"},\"locations\":[{\"physicalLocation\":{\"region\":{\"startLine\":1,\"startColumn\":1,\"endLine\":1,\"endColumn\":2}}}],\"relatedLocations\":[{\"id\":0,\"message\":{\"text\":\"elsewhere\"}}]}]}]}")
+        );
+        let diagnostic = Diagnostic::new(
+            String::from("Invalid character"),
+            Span::new(0, 1, source_map.next_file_id()),
+            vec![],
+        );
+        assert_eq!(
+            diagnostic.to_json(&source_map),
+            "{\"code\":null,\"severity\":\"error\",\"message\":\"Invalid character\",\"labels\":[],\"help\":null}"
+        );
+        assert!(sarif(&[diagnostic], &source_map).contains("\"locations\":[]"));
+        assert_eq!(
+            sarif(&[], &source_map),
+            "{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\",\"runs\":[{\"tool\":{\"driver\":{\"name\":\"lox\",\"rules\":[]}},\"columnKind\":\"unicodeCodePoints\",\"results\":[]}]}"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::error_codes::ErrorCode;
+use crate::errors::RuntimeError;
+use crate::tokens::Token;
+use crate::value::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub struct Environment {
+    values: HashMap<String, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Environment {
+            values: HashMap::new(),
+            enclosing: None,
+        }
+    }
+
+    pub fn new_enclosed(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    // Redefining an existing variable is allowed, it simply overwrites the old value
+    pub fn define(&mut self, name: String, value: Value) {
+        self.values.insert(name, value);
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
+        match self.lookup(name.lexeme.as_ref()) {
+            Some(value) => Ok(value),
+            None => Err(RuntimeError::new(
+                ErrorCode::UndefinedVariable,
+                format!("Undefined variable '{}'", name.lexeme),
+                name,
+            )),
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        if let Some(value) = self.values.get(name) {
+            return Some(value.clone());
+        }
+
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().lookup(name),
+            None => None,
+        }
+    }
+
+    // Every name visible from this environment, used to suggest similar names
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.values.keys().cloned().collect();
+        if let Some(enclosing) = &self.enclosing {
+            names.extend(enclosing.borrow().names());
+        }
+
+        names
+    }
+
+    // Walks exactly `distance` environments up the chain, as computed by the resolver
+    fn ancestor(
+        environment: &Rc<RefCell<Environment>>,
+        distance: usize,
+    ) -> Rc<RefCell<Environment>> {
+        let mut environment = Rc::clone(environment);
+        for _ in 0..distance {
+            let enclosing = match &environment.borrow().enclosing {
+                Some(enclosing) => Rc::clone(enclosing),
+                None => break,
+            };
+            environment = enclosing;
+        }
+
+        environment
+    }
+
+    pub fn get_at(
+        environment: &Rc<RefCell<Environment>>,
+        distance: usize,
+        name: &str,
+    ) -> Option<Value> {
+        Environment::ancestor(environment, distance)
+            .borrow()
+            .values
+            .get(name)
+            .cloned()
+    }
+
+    pub fn assign_at(
+        environment: &Rc<RefCell<Environment>>,
+        distance: usize,
+        name: &Token,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        let ancestor = Environment::ancestor(environment, distance);
+        let mut ancestor = ancestor.borrow_mut();
+        match ancestor.values.get_mut(name.lexeme.as_ref()) {
+            Some(slot) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(RuntimeError::new(
+                ErrorCode::UndefinedVariable,
+                format!("Undefined variable '{}'", name.lexeme),
+                name,
+            )),
+        }
+    }
+
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        if let Some(slot) = self.values.get_mut(name.lexeme.as_ref()) {
+            *slot = value;
+            return Ok(());
+        }
+
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err(RuntimeError::new(
+                ErrorCode::UndefinedVariable,
+                format!("Undefined variable '{}'", name.lexeme),
+                name,
+            )),
+        }
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
+}
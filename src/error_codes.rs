@@ -0,0 +1,483 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+// Generates the `ErrorCode` enum from the table below. Codes are stable: once published a
+// code keeps its meaning, so new errors get new codes and removed ones are not reused.
+// The first digits are the phase: 01 lexical, 02 parse, 03 resolve, 04 runtime
+macro_rules! error_codes {
+    ($($name:ident => $code:literal, $severity:ident, $explanation:literal,)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum ErrorCode {
+            $($name,)*
+        }
+
+        pub const ERROR_CODES: &[ErrorCode] = &[$(ErrorCode::$name,)*];
+
+        impl ErrorCode {
+            pub fn code(&self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => $code,)*
+                }
+            }
+
+            pub fn severity(&self) -> Severity {
+                match self {
+                    $(ErrorCode::$name => Severity::$severity,)*
+                }
+            }
+
+            // Long description shown by `lox explain`
+            pub fn explanation(&self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => $explanation,)*
+                }
+            }
+
+            pub fn from_code(code: &str) -> Option<ErrorCode> {
+                match code {
+                    $($code => Some(ErrorCode::$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    UnterminatedString => "E0101", Error, r#"A string literal is not closed before the end of the file.
+
+Erroneous code example:
+
+    var greeting = "hello;
+    print greeting;
+
+Strings can span several lines, so everything up to the end of the file is taken as
+part of the string. Add the closing quote:
+
+    var greeting = "hello";
+    print greeting;
+"#,
+    InvalidCharacter => "E0102", Error, r#"A character that does not start any token appears outside of a string or a comment.
+
+Erroneous code example:
+
+    var total = 10 # 2;
+
+Remove the character, or put it inside a string:
+
+    var total = 10 * 2;
+"#,
+    UnterminatedBlockComment => "E0103", Error, r#"A block comment is not closed before the end of the file.
+
+Erroneous code example:
+
+    /* outer /* inner */
+    print 1;
+
+Block comments nest, so every `/*` needs its own `*/`:
+
+    /* outer /* inner */ */
+    print 1;
+"#,
+    InvalidNumber => "E0104", Error, r#"A number literal is malformed, such as a digit that is not valid for its base or
+an exponent without digits.
+
+Erroneous code example:
+
+    var mask = 0b1021;
+    var big = 1e;
+
+Binary numbers only use 0 and 1, octal 0 to 7 and hexadecimal 0 to 9 and a to f.
+Exponents need at least one digit:
+
+    var mask = 0b1011;
+    var big = 1e6;
+"#,
+    InvalidDigitSeparator => "E0105", Error, r#"A digit separator `_` is not placed between two digits.
+
+Erroneous code example:
+
+    var million = 1__000_000_;
+
+Separators must follow a digit and be followed by one:
+
+    var million = 1_000_000;
+"#,
+    InvalidEscape => "E0106", Error, r#"A backslash in a string is not followed by a known escape sequence.
+
+Erroneous code example:
+
+    print "C:\path";
+
+The supported escapes are `\n`, `\t`, `\r`, `\0`, `\\`, `\"` and `\u{...}`. Escape the
+backslash itself to keep it:
+
+    print "C:\\path";
+"#,
+    InvalidUnicodeEscape => "E0107", Error, r#"A `\u` escape is not a braced hexadecimal Unicode code point.
+
+Erroneous code example:
+
+    print "\u00e9";
+    print "\u{d800}";
+
+Put from 1 to 6 hex digits between braces, naming a code point that is not a surrogate
+and not above 10FFFF:
+
+    print "\u{e9}";
+"#,
+    InvalidUtf8 => "E0108", Error, r#"The source file contains bytes that are not valid UTF-8.
+
+Erroneous code example, saved as Latin-1 where `é` is the single byte E9:
+
+    print "café";
+
+The invalid bytes are replaced by spaces so the rest of the file can still be checked.
+Save the file as UTF-8, where `é` is the two bytes C3 A9:
+
+    print "café";
+
+An existing file can be converted, for example with:
+
+    iconv -f latin1 -t utf-8 script.lox > script.utf8.lox
+"#,
+    ExpectedToken => "E0201", Error, r#"The parser needs a specific token at this point, such as a closing parenthesis or
+a semicolon, and found something else.
+
+Erroneous code example:
+
+    print (1 + 2;
+
+Add the missing token:
+
+    print (1 + 2);
+"#,
+    ExpectedExpression => "E0202", Error, r#"An expression is needed at this point but the token found cannot start one.
+
+Erroneous code example:
+
+    var a = ;
+
+Write the missing expression:
+
+    var a = 1;
+"#,
+    InvalidAssignmentTarget => "E0203", Error, r#"The left side of `=` is not something that can be assigned to.
+
+Erroneous code example:
+
+    var a = 1;
+    a + 1 = 3;
+
+Only variables and fields can be assigned:
+
+    var a = 1;
+    a = 3;
+"#,
+    TooManyArguments => "E0204", Error, r#"A function declaration or a call has more than 255 parameters or arguments.
+
+Erroneous code example:
+
+    fun f(a1, a2, a3, /* ... */ a256) {}
+
+Group related values into an instance and pass that instead:
+
+    class Options {}
+    fun f(options) {}
+"#,
+    LocalInOwnInitializer => "E0301", Error, r#"A local variable is read in its own initializer.
+
+Erroneous code example:
+
+    var a = 1;
+    {
+        var a = a + 1;
+    }
+
+The new `a` shadows the outer one from its declaration, so its initializer would read
+a variable that has no value yet. Use another name:
+
+    var a = 1;
+    {
+        var b = a + 1;
+    }
+"#,
+    DuplicateVariable => "E0302", Error, r#"A variable is declared twice in the same local scope.
+
+Erroneous code example:
+
+    fun f() {
+        var a = 1;
+        var a = 2;
+    }
+
+Assign the existing variable instead of declaring it again:
+
+    fun f() {
+        var a = 1;
+        a = 2;
+    }
+"#,
+    TopLevelReturn => "E0303", Error, r#"A `return` statement appears outside of any function.
+
+Erroneous code example:
+
+    return 1;
+
+`return` only makes sense inside a function body:
+
+    fun f() {
+        return 1;
+    }
+"#,
+    ReturnValueFromInitializer => "E0304", Error, r#"An `init` method returns a value.
+
+Erroneous code example:
+
+    class Point {
+        init(x) {
+            return x;
+        }
+    }
+
+Initializers always return the new instance. Store the value in a field instead, a
+bare `return;` is still allowed:
+
+    class Point {
+        init(x) {
+            this.x = x;
+        }
+    }
+"#,
+    ThisOutsideClass => "E0305", Error, r#"`this` is used outside of a method.
+
+Erroneous code example:
+
+    fun describe() {
+        print this.name;
+    }
+
+`this` refers to the instance a method is called on. Declare the function as a method:
+
+    class Person {
+        describe() {
+            print this.name;
+        }
+    }
+"#,
+    SuperOutsideClass => "E0306", Error, r#"`super` is used outside of a method.
+
+Erroneous code example:
+
+    fun f() {
+        super.f();
+    }
+
+`super` looks up methods of the superclass, so it is only valid inside a subclass
+method:
+
+    class A { f() {} }
+    class B < A {
+        f() {
+            super.f();
+        }
+    }
+"#,
+    SuperWithoutSuperclass => "E0307", Error, r#"`super` is used in a class that does not inherit from another class.
+
+Erroneous code example:
+
+    class A {
+        f() {
+            super.f();
+        }
+    }
+
+Declare the superclass the method is meant to call:
+
+    class Base { f() {} }
+    class A < Base {
+        f() {
+            super.f();
+        }
+    }
+"#,
+    SelfInheritance => "E0308", Error, r#"A class names itself as its superclass.
+
+Erroneous code example:
+
+    class A < A {}
+
+Inherit from another class, or drop the superclass:
+
+    class Base {}
+    class A < Base {}
+"#,
+    UndefinedVariable => "E0401", Error, r#"A variable is read or assigned before being declared.
+
+Erroneous code example:
+
+    print count;
+
+Declare the variable with `var` first:
+
+    var count = 0;
+    print count;
+"#,
+    UndefinedProperty => "E0402", Error, r#"An instance has no field or method with the requested name.
+
+Erroneous code example:
+
+    class Point {}
+    var p = Point();
+    print p.x;
+
+Set the field before reading it, for example in the initializer:
+
+    class Point {
+        init() {
+            this.x = 0;
+        }
+    }
+    var p = Point();
+    print p.x;
+"#,
+    NotCallable => "E0403", Error, r#"A value that is neither a function nor a class is called.
+
+Erroneous code example:
+
+    var name = "lox";
+    name();
+
+Only functions, methods and classes can be called:
+
+    fun name() {
+        return "lox";
+    }
+    name();
+"#,
+    ArityMismatch => "E0404", Error, r#"A function or a class is called with a number of arguments different from the
+number of parameters it declares.
+
+Erroneous code example:
+
+    fun add(a, b) {
+        return a + b;
+    }
+    add(1);
+
+Pass one argument per parameter:
+
+    fun add(a, b) {
+        return a + b;
+    }
+    add(1, 2);
+"#,
+    InvalidOperand => "E0405", Error, r#"An operator is applied to values of the wrong type.
+
+Erroneous code example:
+
+    print "total: " + 3;
+    print -"a";
+
+Arithmetic and comparison operators need numbers, and `+` needs two numbers or two
+strings:
+
+    print "total: " + "3";
+    print -1;
+"#,
+    NotAnInstance => "E0406", Error, r#"A property is read or set on a value that is not an instance.
+
+Erroneous code example:
+
+    var n = 1;
+    n.x = 2;
+
+Only instances of classes have fields:
+
+    class Box {}
+    var n = Box();
+    n.x = 2;
+"#,
+    SuperclassNotClass => "E0407", Error, r#"The superclass of a class is not a class.
+
+Erroneous code example:
+
+    var Base = "base";
+    class A < Base {}
+
+Inherit from a class:
+
+    class Base {}
+    class A < Base {}
+"#,
+    InvalidOperator => "E0408", Error, r#"The interpreter met an operator it does not know how to evaluate.
+
+Erroneous code example:
+
+    There is none. The parser only produces operators the interpreter supports, so no
+    Lox program should trigger this error.
+
+This error means there is a bug in the interpreter itself and there is no fix to make
+on the user side. Please report it along with the script that triggers it.
+"#,
+    StackOverflow => "E0409", Error, r#"Function calls are nested too deeply, usually because of a recursion that never
+reaches its base case.
+
+Erroneous code example:
+
+    fun count(n) {
+        return count(n + 1);
+    }
+    count(0);
+
+Every recursion needs a case that stops it, and calls can only nest up to 1000 deep:
+
+    fun count(n) {
+        if (n == 10) return n;
+        return count(n + 1);
+    }
+    count(0);
+"#,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorCode, ERROR_CODES};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_are_unique_and_round_trip() {
+        let mut seen = HashSet::new();
+        for code in ERROR_CODES {
+            assert!(seen.insert(code.code()), "duplicate code {code}");
+            assert_eq!(ErrorCode::from_code(code.code()), Some(*code));
+            assert!(code.explanation().contains("\n\nErroneous code example"));
+        }
+        assert_eq!(
+            ErrorCode::from_code("E0101"),
+            Some(ErrorCode::UnterminatedString)
+        );
+        assert_eq!(ErrorCode::from_code("E9999"), None);
+    }
+}
@@ -1,11 +1,17 @@
+use crate::diagnostic::{Diagnostic, Label};
+use crate::error_codes::{ErrorCode, Severity};
+use crate::source_map::Span;
+use crate::tokens::Token;
 use std::error::Error;
 use std::fmt;
 
 #[derive(Debug)]
 pub struct LexicalError {
+    code: ErrorCode,
     message: String,
     line: usize,
-    position: usize,
+    pub span: Span,
+    labels: Vec<Label>,
 }
 
 impl fmt::Display for LexicalError {
@@ -13,7 +19,7 @@ impl fmt::Display for LexicalError {
         write!(
             f,
             "Error: {}. Line {}, position {}",
-            self.message, self.line, self.position
+            self.message, self.line, self.span
         )
     }
 }
@@ -21,11 +27,255 @@ impl fmt::Display for LexicalError {
 impl Error for LexicalError {}
 
 impl LexicalError {
-    pub fn new(message: String, line: usize, position: usize) -> LexicalError {
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn new(code: ErrorCode, message: String, line: usize, span: Span) -> LexicalError {
         LexicalError {
+            code,
             message,
             line,
-            position,
+            span,
+            labels: vec![],
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: &str) -> LexicalError {
+        self.labels.push(Label {
+            span,
+            message: String::from(message),
+        });
+        self
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(self.message.clone(), self.span, self.labels.clone()).with_code(self.code)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    code: ErrorCode,
+    message: String,
+    line: usize,
+    pub span: Span,
+    labels: Vec<Label>,
+    help: Option<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Error: {}. Line {}, position {}",
+            self.message, self.line, self.span
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+impl ParseError {
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn new(code: ErrorCode, message: String, token: &Token) -> ParseError {
+        ParseError {
+            code,
+            message,
+            line: token.line,
+            span: token.span,
+            labels: vec![],
+            help: None,
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: &str) -> ParseError {
+        self.labels.push(Label {
+            span,
+            message: String::from(message),
+        });
+        self
+    }
+
+    // A missing help keeps the current one
+    pub fn with_help(mut self, help: Option<String>) -> ParseError {
+        self.help = help.or(self.help);
+        self
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(self.message.clone(), self.span, self.labels.clone())
+            .with_code(self.code)
+            .with_help(self.help.clone())
+    }
+}
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    code: ErrorCode,
+    message: String,
+    line: usize,
+    pub span: Span,
+    help: Option<String>,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Error: {}. Line {}, position {}",
+            self.message, self.line, self.span
+        )
+    }
+}
+
+impl Error for RuntimeError {}
+
+impl RuntimeError {
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn new(code: ErrorCode, message: String, token: &Token) -> RuntimeError {
+        RuntimeError {
+            code,
+            message,
+            line: token.line,
+            span: token.span,
+            help: None,
+        }
+    }
+
+    // A missing help keeps the current one
+    pub fn with_help(mut self, help: Option<String>) -> RuntimeError {
+        self.help = help.or(self.help);
+        self
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(self.message.clone(), self.span, vec![])
+            .with_code(self.code)
+            .with_help(self.help.clone())
+    }
+}
+
+#[derive(Debug)]
+pub struct ResolveError {
+    code: ErrorCode,
+    message: String,
+    line: usize,
+    pub span: Span,
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Error: {}. Line {}, position {}",
+            self.message, self.line, self.span
+        )
+    }
+}
+
+impl Error for ResolveError {}
+
+impl ResolveError {
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn new(code: ErrorCode, message: String, token: &Token) -> ResolveError {
+        ResolveError {
+            code,
+            message,
+            line: token.line,
+            span: token.span,
         }
     }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(self.message.clone(), self.span, vec![]).with_code(self.code)
+    }
+}
+
+// Error of any phase, for code that handles them all the same way such as reporting
+#[derive(Debug)]
+pub enum LoxError {
+    Lexical(LexicalError),
+    Parse(ParseError),
+    Resolve(ResolveError),
+    Runtime(RuntimeError),
+}
+
+impl LoxError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            LoxError::Lexical(e) => e.code(),
+            LoxError::Parse(e) => e.code(),
+            LoxError::Resolve(e) => e.code(),
+            LoxError::Runtime(e) => e.code(),
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.code().severity()
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            LoxError::Lexical(e) => e.span,
+            LoxError::Parse(e) => e.span,
+            LoxError::Resolve(e) => e.span,
+            LoxError::Runtime(e) => e.span,
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        match self {
+            LoxError::Lexical(e) => e.diagnostic(),
+            LoxError::Parse(e) => e.diagnostic(),
+            LoxError::Resolve(e) => e.diagnostic(),
+            LoxError::Runtime(e) => e.diagnostic(),
+        }
+    }
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoxError::Lexical(e) => e.fmt(f),
+            LoxError::Parse(e) => e.fmt(f),
+            LoxError::Resolve(e) => e.fmt(f),
+            LoxError::Runtime(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for LoxError {}
+
+impl From<LexicalError> for LoxError {
+    fn from(e: LexicalError) -> Self {
+        LoxError::Lexical(e)
+    }
+}
+
+impl From<ParseError> for LoxError {
+    fn from(e: ParseError) -> Self {
+        LoxError::Parse(e)
+    }
+}
+
+impl From<ResolveError> for LoxError {
+    fn from(e: ResolveError) -> Self {
+        LoxError::Resolve(e)
+    }
+}
+
+impl From<RuntimeError> for LoxError {
+    fn from(e: RuntimeError) -> Self {
+        LoxError::Runtime(e)
+    }
 }
@@ -1,19 +1,61 @@
 use crate::tokens::{Literal as LiteralType, Token};
+use std::cell::Cell;
+
+// Number of scopes between a variable and its declaration, filled in by the resolver.
+// It stays None for globals
+pub type Depth = Cell<Option<usize>>;
 
 pub enum Expr {
     Unary {
-        operator: Token,
+        operator: Token<'static>,
         right: Box<Expr>,
     },
     Binary {
         left: Box<Expr>,
-        operator: Token,
+        operator: Token<'static>,
         right: Box<Expr>,
     },
+    Call {
+        callee: Box<Expr>,
+        paren: Token<'static>,
+        arguments: Vec<Expr>,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token<'static>,
+    },
+    Set {
+        object: Box<Expr>,
+        name: Token<'static>,
+        value: Box<Expr>,
+    },
+    This {
+        keyword: Token<'static>,
+        depth: Depth,
+    },
+    Super {
+        keyword: Token<'static>,
+        method: Token<'static>,
+        depth: Depth,
+    },
     Grouping {
         expr: Box<Expr>,
     },
     Literal {
-        value: LiteralType,
+        value: LiteralType<'static>,
+    },
+    Logical {
+        left: Box<Expr>,
+        operator: Token<'static>,
+        right: Box<Expr>,
+    },
+    Variable {
+        name: Token<'static>,
+        depth: Depth,
+    },
+    Assign {
+        name: Token<'static>,
+        value: Box<Expr>,
+        depth: Depth,
     },
 }
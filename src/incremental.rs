@@ -0,0 +1,312 @@
+use crate::errors::LexicalError;
+use crate::source_map::Span;
+use crate::token_scanner::Scanner;
+use crate::tokens::{Literal, Token, TokenType};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+
+// Replacement of the `start..end` byte range of a source by `text`
+pub struct Edit<'e> {
+    pub start: usize,
+    pub end: usize,
+    pub text: &'e str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EditError {
+    ReversedRange { start: usize, end: usize },
+    OutOfBounds { end: usize, len: usize },
+    NotCharBoundary { offset: usize },
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::ReversedRange { start, end } => {
+                write!(f, "Error: edit starts at {start} after its end {end}")
+            }
+            EditError::OutOfBounds { end, len } => {
+                write!(
+                    f,
+                    "Error: edit ends at {end} past the end of the source {len}"
+                )
+            }
+            EditError::NotCharBoundary { offset } => {
+                write!(f, "Error: edit offset {offset} is inside a character")
+            }
+        }
+    }
+}
+
+impl Error for EditError {}
+
+impl Edit<'_> {
+    // The range must be ordered, within the source and on character boundaries
+    pub fn check(&self, source: &str) -> Result<(), EditError> {
+        if self.start > self.end {
+            return Err(EditError::ReversedRange {
+                start: self.start,
+                end: self.end,
+            });
+        }
+        if self.end > source.len() {
+            return Err(EditError::OutOfBounds {
+                end: self.end,
+                len: source.len(),
+            });
+        }
+        match [self.start, self.end]
+            .into_iter()
+            .find(|&offset| !source.is_char_boundary(offset))
+        {
+            Some(offset) => Err(EditError::NotCharBoundary { offset }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn apply(&self, source: &str) -> Result<String, EditError> {
+        self.check(source)?;
+        let mut edited = String::from(&source[..self.start]);
+        edited.push_str(self.text);
+        edited.push_str(&source[self.end..]);
+        Ok(edited)
+    }
+
+    // Whether `source` can be the result of applying this edit to a source of `old_len`
+    // bytes
+    fn fits(&self, source: &str, old_len: Option<usize>) -> bool {
+        let edited = Edit {
+            start: self.start,
+            end: self.start.saturating_add(self.text.len()),
+            text: "",
+        };
+        self.start <= self.end
+            && edited.check(source).is_ok()
+            && old_len.is_none_or(|len| {
+                self.end <= len && len - (self.end - self.start) + self.text.len() == source.len()
+            })
+    }
+}
+
+// Tokens of `source`, the text produced by applying `edit` to the source `tokens` were
+// scanned from without errors. Only the tokens around the edit are scanned again, the
+// others are moved to their new position. The result is the one of `Scanner::scan`, which
+// is also what an edit that does not match the sources falls back to
+pub fn relex<'new>(
+    tokens: &[Token<'_>],
+    edit: &Edit,
+    source: &'new str,
+) -> Result<Vec<Token<'new>>, LexicalError> {
+    let file_id = tokens.last().map(|t| t.span.file_id).unwrap_or_default();
+    let scanner = Scanner::new_with_file(source, file_id);
+    // The old source ends where its Eof token is
+    let old_len = tokens
+        .last()
+        .filter(|t| t.token_type == TokenType::Eof)
+        .map(|t| t.span.end);
+    if !edit.fits(source, old_len) {
+        return scanner.scan();
+    }
+    let delta = edit.text.len() as isize - (edit.end - edit.start) as isize;
+
+    // Scanning a token may look at the character following it and at the one after, so a
+    // token is only kept when both are before the edit
+    let kept = tokens
+        .iter()
+        .take_while(|t| t.token_type != TokenType::Eof && t.span.end + 1 < edit.start)
+        .count();
+    let mut relexed: Vec<Token<'new>> = tokens[..kept]
+        .iter()
+        .map(|t| rebase(t, source, 0, 0))
+        .collect();
+    let scanner = match relexed.last() {
+        Some(last) => scanner.resume_at(last.span.end, last.line),
+        None => scanner,
+    };
+
+    // Once a scanned token starts where an old token after the edit starts, the rest of
+    // the source is unchanged and so are the following tokens
+    let edited_end = edit.start + edit.text.len();
+    for item in scanner {
+        let token = item?;
+        if token.span.start >= edited_end {
+            let old_start = token.span.start.wrapping_add_signed(-delta);
+            if let Ok(index) = tokens.binary_search_by_key(&old_start, |t| t.span.start) {
+                let line_delta = token.line as isize - tokens[index].line as isize;
+                relexed.extend(
+                    tokens[index..]
+                        .iter()
+                        .map(|t| rebase(t, source, delta, line_delta)),
+                );
+                return Ok(relexed);
+            }
+        }
+        relexed.push(token);
+    }
+
+    Ok(relexed)
+}
+
+// Same token at its position in the new source, borrowing from it
+fn rebase<'new>(
+    token: &Token<'_>,
+    source: &'new str,
+    delta: isize,
+    line_delta: isize,
+) -> Token<'new> {
+    let start = token.span.start.wrapping_add_signed(delta);
+    let end = token.span.end.wrapping_add_signed(delta);
+    let lexeme = &source[start..end];
+    // Borrowed values are the lexeme of identifiers and the inside of strings
+    let literal = match &token.literal {
+        Some(Literal::Str {
+            value: Cow::Borrowed(_),
+        }) => Some(Literal::Str {
+            value: Cow::Borrowed(match token.token_type {
+                TokenType::String => &lexeme[1..lexeme.len() - 1],
+                _ => lexeme,
+            }),
+        }),
+        literal => literal.clone().map(Literal::into_owned),
+    };
+
+    Token::new(
+        token.token_type,
+        lexeme,
+        token.line.wrapping_add_signed(line_delta),
+        Span::new(start, end, token.span.file_id),
+        literal,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{relex, Edit, EditError};
+    use crate::test_utils::{random_source, Random};
+    use crate::token_scanner::Scanner;
+
+    #[test]
+    fn test_relex() {
+        let old = "var name = \"a\\tb\";\nprint name + 12;\n";
+        let tokens = Scanner::new(old).scan().unwrap();
+
+        // Renaming keeps every other token but moves the ones after it
+        let edit = Edit {
+            start: 4,
+            end: 8,
+            text: "longer_name",
+        };
+        let new = edit.apply(old).unwrap();
+        let relexed = relex(&tokens, &edit, &new).unwrap();
+        assert_eq!(relexed, Scanner::new(&new).scan().unwrap());
+        assert_eq!(relexed[1].lexeme, "longer_name");
+        assert_eq!(relexed[6].span.start, tokens[6].span.start + 7);
+
+        // A line break shifts the lines of the following tokens
+        let edit = Edit {
+            start: 19,
+            end: 19,
+            text: "\n\n",
+        };
+        let new = edit.apply(old).unwrap();
+        let relexed = relex(&tokens, &edit, &new).unwrap();
+        assert_eq!(relexed, Scanner::new(&new).scan().unwrap());
+        assert_eq!(relexed[5].line, 4);
+
+        // Opening a comment swallows the rest of the line
+        let edit = Edit {
+            start: 25,
+            end: 25,
+            text: "//",
+        };
+        let new = edit.apply(old).unwrap();
+        let relexed = relex(&tokens, &edit, &new).unwrap();
+        assert_eq!(relexed, Scanner::new(&new).scan().unwrap());
+
+        let edit = Edit {
+            start: 11,
+            end: 11,
+            text: "\"",
+        };
+        let new = edit.apply(old).unwrap();
+        let error = relex(&tokens, &edit, &new).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            Scanner::new(&new).scan().err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_invalid_edits() {
+        let old = "print \"é\";";
+        let edit = |start, end| Edit {
+            start,
+            end,
+            text: "x",
+        };
+        assert_eq!(
+            edit(3, 2).apply(old),
+            Err(EditError::ReversedRange { start: 3, end: 2 })
+        );
+        assert_eq!(
+            edit(0, 20).apply(old),
+            Err(EditError::OutOfBounds { end: 20, len: 11 })
+        );
+        assert_eq!(
+            edit(8, 8).apply(old),
+            Err(EditError::NotCharBoundary { offset: 8 })
+        );
+
+        // An edit that does not match the sources falls back to a full scan
+        let tokens = Scanner::new(old).scan().unwrap();
+        let new = "print 1;";
+        for (start, end) in [(3, 2), (0, 20), (8, 8), (0, 1)] {
+            let relexed = relex(&tokens, &edit(start, end), new).unwrap();
+            assert_eq!(relexed, Scanner::new(new).scan().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_relex_matches_full_scan() {
+        let fragments = [
+            " ", "\n", "a", "bc", "1", "2.5", ".", "=", "!", "<", "/", "*", "\"s\"", "\"\\n\"",
+            "// c\n", "/* c */", "é", "0x1F", "e", "_", "\"", "@",
+        ];
+        let mut random = Random(0x2545_F491_4F6C_DD1D);
+        let mut checked = 0;
+        while checked < 1000 {
+            let old = random_source(&mut random, &fragments, 30);
+            let Ok(tokens) = Scanner::new(&old).scan() else {
+                continue;
+            };
+
+            let mut start = random.below(old.len() + 1);
+            while !old.is_char_boundary(start) {
+                start -= 1;
+            }
+            let mut end = start + random.below(old.len() - start + 1).min(6);
+            while !old.is_char_boundary(end) {
+                end += 1;
+            }
+            let text = random_source(&mut random, &fragments, 3);
+            let edit = Edit {
+                start,
+                end,
+                text: &text,
+            };
+            let new = edit.apply(&old).unwrap();
+
+            let expected = Scanner::new(&new).scan();
+            let relexed = relex(&tokens, &edit, &new);
+            match (relexed, expected) {
+                (Ok(relexed), Ok(expected)) => assert_eq!(relexed, expected, "{old:?} -> {new:?}"),
+                (Err(relexed), Err(expected)) => {
+                    assert_eq!(relexed.to_string(), expected.to_string())
+                }
+                (relexed, expected) => panic!("{old:?} -> {new:?}: {relexed:?} != {expected:?}"),
+            }
+            checked += 1;
+        }
+    }
+}
@@ -0,0 +1,742 @@
+use crate::class::{LoxClass, LoxInstance};
+use crate::environment::Environment;
+use crate::error_codes::ErrorCode;
+use crate::errors::RuntimeError;
+use crate::expr::{Depth, Expr};
+use crate::function::{clock, LoxFunction, NativeFunction};
+use crate::stmt::Stmt;
+use crate::tokens::{statement_keywords, Token, TokenType};
+use crate::utils::suggestion;
+use crate::value::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Statements can stop executing either because of an error or because of a `return`,
+// which has to unwind every enclosing block up to the function call
+enum Unwind {
+    Error(RuntimeError),
+    Return(Value),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+// Lox calls nest Rust calls, so unbounded recursion would overflow the native stack and
+// abort the process. Deeper calls fail with a runtime error instead
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+pub struct Interpreter {
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    // Number of Lox function calls in progress
+    depth: usize,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        globals.borrow_mut().define(
+            String::from("clock"),
+            Value::NativeFunction(Rc::new(NativeFunction {
+                name: "clock",
+                arity: 0,
+                function: clock,
+            })),
+        );
+
+        Interpreter {
+            environment: Rc::clone(&globals),
+            globals,
+            depth: 0,
+        }
+    }
+
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), RuntimeError> {
+        for statement in statements {
+            match self.execute(statement) {
+                Ok(()) => {}
+                // A top level return simply stops the script
+                Err(Unwind::Return(_)) => return Ok(()),
+                Err(Unwind::Error(e)) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, stmt: &Stmt) -> Result<(), Unwind> {
+        match stmt {
+            Stmt::Expression { expr } => match (expr, self.evaluate(expr)) {
+                // A lone unknown name, such as `retrun;`, may be a misspelled keyword
+                (Expr::Variable { name, .. }, Err(e))
+                    if e.code() == ErrorCode::UndefinedVariable =>
+                {
+                    let names = self.environment.borrow().names();
+                    let candidates = names.iter().map(String::as_str).chain(statement_keywords());
+                    return Err(e.with_help(suggestion(&name.lexeme, candidates)).into());
+                }
+                (_, result) => {
+                    result?;
+                }
+            },
+            Stmt::Print { expr } => {
+                let value = self.evaluate(expr)?;
+                println!("{value}");
+            }
+            Stmt::Var {
+                name, initializer, ..
+            } => {
+                let value = match initializer {
+                    Some(initializer) => self.evaluate(initializer)?,
+                    None => Value::Nil,
+                };
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.to_string(), value);
+            }
+            Stmt::Function { declaration } => {
+                let function =
+                    LoxFunction::new(Rc::clone(declaration), Rc::clone(&self.environment), false);
+                self.environment.borrow_mut().define(
+                    declaration.name.lexeme.to_string(),
+                    Value::Function(Rc::new(function)),
+                );
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                let superclass = match superclass {
+                    Some(superclass) => match self.evaluate(superclass)? {
+                        Value::Class(class) => Some(class),
+                        _ => {
+                            let token = match superclass {
+                                Expr::Variable { name, .. } => name,
+                                _ => name,
+                            };
+                            return Err(Unwind::Error(RuntimeError::new(
+                                ErrorCode::SuperclassNotClass,
+                                String::from("Superclass must be a class"),
+                                token,
+                            )));
+                        }
+                    },
+                    None => None,
+                };
+
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.to_string(), Value::Nil);
+
+                // Methods of a subclass close over an extra environment holding `super`
+                let previous = superclass.as_ref().map(|superclass| {
+                    let mut environment = Environment::new_enclosed(Rc::clone(&self.environment));
+                    environment.define(String::from("super"), Value::Class(Rc::clone(superclass)));
+                    std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)))
+                });
+
+                let mut class_methods = HashMap::new();
+                for method in methods {
+                    let function = LoxFunction::new(
+                        Rc::clone(method),
+                        Rc::clone(&self.environment),
+                        method.name.lexeme == "init",
+                    );
+                    class_methods.insert(method.name.lexeme.to_string(), Rc::new(function));
+                }
+                let class = LoxClass::new(name.lexeme.to_string(), superclass, class_methods);
+
+                if let Some(previous) = previous {
+                    self.environment = previous;
+                }
+                self.environment
+                    .borrow_mut()
+                    .assign(name, Value::Class(Rc::new(class)))?;
+            }
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Nil,
+                };
+                return Err(Unwind::Return(value));
+            }
+            Stmt::Block { statements } => {
+                let environment = Environment::new_enclosed(Rc::clone(&self.environment));
+                self.execute_block(statements, Rc::new(RefCell::new(environment)))?;
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.execute(then_branch)?;
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch)?;
+                }
+            }
+            Stmt::While { condition, body } => {
+                while self.evaluate(condition)?.is_truthy() {
+                    self.execute(body)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // The previous environment must be restored even when a statement fails
+    fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), Unwind> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.execute(statement));
+        self.environment = previous;
+
+        result
+    }
+
+    fn call(
+        &mut self,
+        callee: Value,
+        paren: &Token,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match callee {
+            Value::Function(function) => {
+                check_arity(function.arity(), arguments.len(), paren)?;
+                let mut environment = Environment::new_enclosed(Rc::clone(&function.closure));
+                for (param, argument) in function.declaration.params.iter().zip(arguments) {
+                    environment.define(param.lexeme.to_string(), argument);
+                }
+
+                if self.depth == MAX_CALL_DEPTH {
+                    return Err(RuntimeError::new(
+                        ErrorCode::StackOverflow,
+                        String::from("Stack overflow"),
+                        paren,
+                    ));
+                }
+                self.depth += 1;
+                let result = self.execute_block(
+                    &function.declaration.body,
+                    Rc::new(RefCell::new(environment)),
+                );
+                self.depth -= 1;
+                let result = match result {
+                    Ok(()) => Value::Nil,
+                    Err(Unwind::Return(value)) => value,
+                    Err(Unwind::Error(e)) => return Err(e),
+                };
+
+                // An initializer always returns the instance, even with an early `return;`
+                if function.is_initializer {
+                    return Ok(
+                        Environment::get_at(&function.closure, 0, "this").unwrap_or(Value::Nil)
+                    );
+                }
+
+                Ok(result)
+            }
+            Value::Class(class) => {
+                let instance =
+                    Value::Instance(Rc::new(RefCell::new(LoxInstance::new(Rc::clone(&class)))));
+                match class.find_method("init") {
+                    Some(initializer) => {
+                        let initializer =
+                            Value::Function(Rc::new(initializer.bind(instance.clone())));
+                        self.call(initializer, paren, arguments)?;
+                    }
+                    None => check_arity(class.arity(), arguments.len(), paren)?,
+                }
+
+                Ok(instance)
+            }
+            Value::NativeFunction(function) => {
+                check_arity(function.arity, arguments.len(), paren)?;
+                Ok((function.function)(&arguments))
+            }
+            _ => Err(RuntimeError::new(
+                ErrorCode::NotCallable,
+                String::from("Can only call functions and classes"),
+                paren,
+            )),
+        }
+    }
+
+    fn look_up_variable(&self, name: &Token, depth: &Depth) -> Result<Value, RuntimeError> {
+        match depth.get() {
+            Some(distance) => Environment::get_at(&self.environment, distance, &name.lexeme)
+                .ok_or_else(|| {
+                    RuntimeError::new(
+                        ErrorCode::UndefinedVariable,
+                        format!("Undefined variable '{}'", name.lexeme),
+                        name,
+                    )
+                }),
+            None => self
+                .globals
+                .borrow()
+                .get(name)
+                .map_err(|e| self.suggest_variable(e, name)),
+        }
+    }
+
+    // Unresolved names are looked up in the globals, but the typo may be of a local
+    fn suggest_variable(&self, error: RuntimeError, name: &Token) -> RuntimeError {
+        let names = self.environment.borrow().names();
+        error.with_help(suggestion(&name.lexeme, names.iter().map(String::as_str)))
+    }
+
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        match expr {
+            Expr::Literal { value } => Ok(Value::from(value)),
+            Expr::Grouping { expr } => self.evaluate(expr),
+            Expr::Get { object, name } => match self.evaluate(object)? {
+                Value::Instance(instance) => LoxInstance::get(&instance, name),
+                _ => Err(RuntimeError::new(
+                    ErrorCode::NotAnInstance,
+                    String::from("Only instances have properties"),
+                    name,
+                )),
+            },
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => match self.evaluate(object)? {
+                Value::Instance(instance) => {
+                    let value = self.evaluate(value)?;
+                    instance.borrow_mut().set(name, value.clone());
+                    Ok(value)
+                }
+                _ => Err(RuntimeError::new(
+                    ErrorCode::NotAnInstance,
+                    String::from("Only instances have fields"),
+                    name,
+                )),
+            },
+            Expr::This { keyword, depth } => self.look_up_variable(keyword, depth),
+            Expr::Super {
+                keyword,
+                method,
+                depth,
+            } => {
+                let superclass = self.look_up_variable(keyword, depth)?;
+                // `this` is always bound in the environment right inside the one holding `super`
+                let object = depth
+                    .get()
+                    .and_then(|distance| {
+                        Environment::get_at(&self.environment, distance.saturating_sub(1), "this")
+                    })
+                    .unwrap_or(Value::Nil);
+                let found = match &superclass {
+                    Value::Class(superclass) => superclass.find_method(method.lexeme.as_ref()),
+                    _ => None,
+                };
+                match found {
+                    Some(found) => Ok(Value::Function(Rc::new(found.bind(object)))),
+                    None => {
+                        let error = RuntimeError::new(
+                            ErrorCode::UndefinedProperty,
+                            format!("Undefined property '{}'", method.lexeme),
+                            method,
+                        );
+                        let names = match &superclass {
+                            Value::Class(superclass) => superclass.method_names(),
+                            _ => vec![],
+                        };
+                        let help = suggestion(&method.lexeme, names.iter().map(String::as_str));
+                        Err(error.with_help(help))
+                    }
+                }
+            }
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => {
+                let callee = self.evaluate(callee)?;
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.evaluate(argument))
+                    .collect::<Result<Vec<Value>, RuntimeError>>()?;
+                self.call(callee, paren, arguments)
+            }
+            Expr::Variable { name, depth } => self.look_up_variable(name, depth),
+            Expr::Assign { name, value, depth } => {
+                let value = self.evaluate(value)?;
+                match depth.get() {
+                    Some(distance) => {
+                        Environment::assign_at(&self.environment, distance, name, value.clone())?
+                    }
+                    None => {
+                        // The globals borrow must end before the suggestion looks at them
+                        let assigned = self.globals.borrow_mut().assign(name, value.clone());
+                        assigned.map_err(|e| self.suggest_variable(e, name))?
+                    }
+                }
+                Ok(value)
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(left)?;
+                let short_circuit = match operator.token_type {
+                    TokenType::Or => left.is_truthy(),
+                    _ => !left.is_truthy(),
+                };
+                if short_circuit {
+                    return Ok(left);
+                }
+
+                self.evaluate(right)
+            }
+            Expr::Unary { operator, right } => {
+                let right = self.evaluate(right)?;
+                match operator.token_type {
+                    TokenType::Bang => Ok(Value::Bool(!right.is_truthy())),
+                    TokenType::Minus => {
+                        let value = number_operand(operator, &right)?;
+                        Ok(Value::Number(-value))
+                    }
+                    _ => Err(RuntimeError::new(
+                        ErrorCode::InvalidOperator,
+                        String::from("Invalid unary operator"),
+                        operator,
+                    )),
+                }
+            }
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                match operator.token_type {
+                    TokenType::Plus => match (left, right) {
+                        (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
+                        (Value::Str(l), Value::Str(r)) => Ok(Value::Str(l + &r)),
+                        _ => Err(RuntimeError::new(
+                            ErrorCode::InvalidOperand,
+                            String::from("Operands must be two numbers or two strings"),
+                            operator,
+                        )),
+                    },
+                    TokenType::Minus => {
+                        let (l, r) = number_operands(operator, &left, &right)?;
+                        Ok(Value::Number(l - r))
+                    }
+                    TokenType::Star => {
+                        let (l, r) = number_operands(operator, &left, &right)?;
+                        Ok(Value::Number(l * r))
+                    }
+                    TokenType::Slash => {
+                        let (l, r) = number_operands(operator, &left, &right)?;
+                        Ok(Value::Number(l / r))
+                    }
+                    TokenType::Gt => {
+                        let (l, r) = number_operands(operator, &left, &right)?;
+                        Ok(Value::Bool(l > r))
+                    }
+                    TokenType::Gte => {
+                        let (l, r) = number_operands(operator, &left, &right)?;
+                        Ok(Value::Bool(l >= r))
+                    }
+                    TokenType::Lt => {
+                        let (l, r) = number_operands(operator, &left, &right)?;
+                        Ok(Value::Bool(l < r))
+                    }
+                    TokenType::Lte => {
+                        let (l, r) = number_operands(operator, &left, &right)?;
+                        Ok(Value::Bool(l <= r))
+                    }
+                    TokenType::EqEq => Ok(Value::Bool(left == right)),
+                    TokenType::BangEq => Ok(Value::Bool(left != right)),
+                    _ => Err(RuntimeError::new(
+                        ErrorCode::InvalidOperator,
+                        String::from("Invalid binary operator"),
+                        operator,
+                    )),
+                }
+            }
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+fn check_arity(arity: usize, count: usize, paren: &Token) -> Result<(), RuntimeError> {
+    if arity != count {
+        return Err(RuntimeError::new(
+            ErrorCode::ArityMismatch,
+            format!("Expected {arity} arguments but got {count}"),
+            paren,
+        ));
+    }
+
+    Ok(())
+}
+
+fn number_operand(operator: &Token, operand: &Value) -> Result<f64, RuntimeError> {
+    match operand {
+        Value::Number(value) => Ok(*value),
+        _ => Err(RuntimeError::new(
+            ErrorCode::InvalidOperand,
+            String::from("Operand must be a number"),
+            operator,
+        )),
+    }
+}
+
+fn number_operands(
+    operator: &Token,
+    left: &Value,
+    right: &Value,
+) -> Result<(f64, f64), RuntimeError> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => Ok((*l, *r)),
+        _ => Err(RuntimeError::new(
+            ErrorCode::InvalidOperand,
+            String::from("Operands must be numbers"),
+            operator,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interpreter, MAX_CALL_DEPTH};
+    use crate::error_codes::ErrorCode;
+    use crate::errors::RuntimeError;
+    use crate::parser::Parser;
+    use crate::resolver::Resolver;
+    use crate::stmt::Stmt;
+    use crate::token_scanner::Scanner;
+    use crate::value::Value;
+    use std::thread;
+
+    // Runs the program, then evaluates the expression statement `result` in the same interpreter
+    fn run(program: &str, result: &str) -> Result<Value, RuntimeError> {
+        let mut interpreter = Interpreter::new();
+        let (statements, errors) = Parser::new(Scanner::new(program)).parse();
+        assert!(errors.is_empty());
+        Resolver::new().resolve(&statements).unwrap();
+        interpreter.interpret(&statements)?;
+
+        match Parser::new(Scanner::new(result)).parse().0.remove(0) {
+            Stmt::Expression { expr } => interpreter.evaluate(&expr),
+            _ => panic!("Expected an expression statement"),
+        }
+    }
+
+    fn evaluate(source: &str) -> Result<Value, RuntimeError> {
+        run("", source)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(evaluate("1 + 2 * 3;").unwrap(), Value::Number(7.0));
+        assert_eq!(evaluate("-(4 - 6) / 4;").unwrap(), Value::Number(0.5));
+        assert_eq!(
+            evaluate("\"foo\" + \"bar\";").unwrap(),
+            Value::Str(String::from("foobar"))
+        );
+        assert_eq!(evaluate("1 < 2 == true;").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("nil == false;").unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("!nil;").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("!0;").unwrap(), Value::Bool(false));
+        assert_eq!(
+            evaluate("nil or \"yes\";").unwrap(),
+            Value::Str(String::from("yes"))
+        );
+        assert_eq!(evaluate("1 and false;").unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_runtime_error() {
+        let err = evaluate("\"a\" - 1;").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Operands must be numbers. Line 1, position 4..5"
+        );
+
+        let err = evaluate("-\"a\";").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Operand must be a number. Line 1, position 0..1"
+        );
+
+        let err = evaluate("undefined;").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Undefined variable 'undefined'. Line 1, position 0..9"
+        );
+    }
+
+    #[test]
+    fn test_block_scope() {
+        let program = "var a = 1;\nvar b = 2;\n{\n  var a = 10;\n  b = a + b;\n}\n";
+        assert_eq!(run(program, "a;").unwrap(), Value::Number(1.0));
+        assert_eq!(run(program, "b;").unwrap(), Value::Number(12.0));
+
+        let err = run("{ var inner = 1; }\n", "inner;").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Undefined variable 'inner'. Line 1, position 0..5"
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        let program = "fun fib(n) {\n  if (n < 2) return n;\n  return fib(n - 1) + fib(n - 2);\n}\nfun noop() { return; }\n";
+        assert_eq!(run(program, "fib(10);").unwrap(), Value::Number(55.0));
+        assert_eq!(run(program, "noop();").unwrap(), Value::Nil);
+        assert!(matches!(
+            run(program, "clock();").unwrap(),
+            Value::Number(_)
+        ));
+
+        let err = run(program, "fib(1, 2);").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Expected 1 arguments but got 2. Line 1, position 8..9"
+        );
+
+        let err = run(program, "\"fib\"(1);").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Can only call functions and classes. Line 1, position 7..8"
+        );
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        // Like `main`, give the interpreter enough stack to reach the call depth limit
+        let deep = thread::Builder::new().stack_size(256 * 1024 * 1024).spawn(|| {
+            let program = "fun f(n) {\n  if (n == 0) return 0;\n  return 1 + f(n - 1);\n}\nclass A {\n  init(n) { if (n > 0) A(n - 1); }\n}\n";
+            let limit = MAX_CALL_DEPTH - 1;
+            assert_eq!(
+                run(program, &format!("f({limit});")).unwrap(),
+                Value::Number(limit as f64)
+            );
+            for call in ["f(100000);", "A(100000);"] {
+                let err = run(program, call).err().unwrap();
+                assert_eq!(err.code(), ErrorCode::StackOverflow, "{call}");
+            }
+        });
+        deep.unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn test_closures() {
+        let program = "fun makeCounter() {\n  var i = 0;\n  fun count() {\n    i = i + 1;\n    return i;\n  }\n  return count;\n}\nvar counter = makeCounter();\ncounter();\ncounter();\n";
+        assert_eq!(run(program, "counter();").unwrap(), Value::Number(3.0));
+        assert_eq!(
+            run(program, "makeCounter()();").unwrap(),
+            Value::Number(1.0)
+        );
+    }
+
+    #[test]
+    fn test_classes() {
+        let program = "class Point {\n  init(x, y) {\n    this.x = x;\n    this.y = y;\n    return;\n  }\n  sum() { return this.x + this.y; }\n}\nvar p = Point(1, 2);\nvar sum = p.sum;\np.x = 10;\n";
+        assert_eq!(run(program, "sum();").unwrap(), Value::Number(12.0));
+        assert_eq!(
+            run(program, "p.init(3, 4) == p;").unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(run(program, "p.y;").unwrap(), Value::Number(2.0));
+
+        let err = run(program, "p.z;").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Undefined property 'z'. Line 1, position 2..3"
+        );
+
+        let err = run(program, "Point(1);").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Expected 2 arguments but got 1. Line 1, position 7..8"
+        );
+    }
+
+    #[test]
+    fn test_inheritance() {
+        let program = "class A {\n  name() { return \"A\"; }\n  greet() { return \"hello \" + this.name(); }\n}\nclass B < A {\n  name() { return \"B from \" + super.name(); }\n}\n";
+        assert_eq!(
+            run(program, "B().greet();").unwrap(),
+            Value::Str(String::from("hello B from A"))
+        );
+
+        let err = run("var NotAClass = 1;\nclass C < NotAClass {}\n", "C;")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Superclass must be a class. Line 2, position 29..38"
+        );
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let help =
+            |program: &str, result: &str| run(program, result).err().unwrap().diagnostic().help;
+        let program = "var count = 1;\nclass A { greet() {} }\nclass B < A { init() { this.size = 1; } hi() { super.gret(); } }\nvar b = B();\nfun f() { var total = 2; return totl; }\n";
+        assert_eq!(
+            help(program, "conut;"),
+            Some(String::from("did you mean 'count'?"))
+        );
+        assert_eq!(
+            help(program, "f();"),
+            Some(String::from("did you mean 'total'?"))
+        );
+        assert_eq!(
+            help(program, "b.szie;"),
+            Some(String::from("did you mean 'size'?"))
+        );
+        assert_eq!(
+            help(program, "b.gret;"),
+            Some(String::from("did you mean 'greet'?"))
+        );
+        assert_eq!(
+            help(program, "b.hi();"),
+            Some(String::from("did you mean 'greet'?"))
+        );
+        assert_eq!(help(program, "unrelated;"), None);
+        // A lone name may be a misspelled statement keyword
+        assert_eq!(
+            help("retrun;", "nil;"),
+            Some(String::from("did you mean 'return'?"))
+        );
+        assert_eq!(
+            help("var whiles = 1;\nwhlie;", "nil;"),
+            Some(String::from("did you mean 'while'?"))
+        );
+        assert_eq!(help("whlie + 1;", "nil;"), None);
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let program = "var sum = 0;\nfor (var i = 0; i < 5; i = i + 1) {\n  if (i == 2) sum = sum + 100; else sum = sum + i;\n}\nvar n = 0;\nwhile (n < 3) n = n + 1;\n";
+        assert_eq!(run(program, "sum;").unwrap(), Value::Number(108.0));
+        assert_eq!(run(program, "n;").unwrap(), Value::Number(3.0));
+    }
+}
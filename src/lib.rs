@@ -0,0 +1,20 @@
+pub mod ast;
+pub mod class;
+pub mod diagnostic;
+pub mod environment;
+pub mod error_codes;
+pub mod errors;
+pub mod expr;
+pub mod function;
+pub mod incremental;
+pub mod interpreter;
+pub mod parser;
+pub mod resolver;
+pub mod source_map;
+pub mod stmt;
+#[cfg(test)]
+mod test_utils;
+pub mod token_scanner;
+pub mod tokens;
+pub mod utils;
+pub mod value;
@@ -1,33 +1,325 @@
 use std::env;
+use std::fs;
 use std::io;
-use std::io::Write;
+use std::io::{BufRead, IsTerminal, Read, Write};
+use std::path::Path;
+use std::process;
+use std::thread;
 
-mod ast;
-mod errors;
-mod expr;
-mod token_scanner;
-mod tokens;
-mod utils;
+use lox::diagnostic::{sarif, Diagnostic};
+use lox::error_codes::ErrorCode;
+use lox::errors::LoxError;
+use lox::interpreter::Interpreter;
+use lox::parser::Parser;
+use lox::resolver::Resolver;
+use lox::source_map::SourceMap;
+use lox::token_scanner::{decode_source, Scanner};
 
-fn run_prompt() {
+// Exit codes from sysexits(3)
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_NOINPUT: i32 = 66;
+const EX_SOFTWARE: i32 = 70;
+
+const USAGE: &str =
+    "Usage: lox [--color=auto|always|never] [--error-format=human|json|sarif] [script | -]
+       lox explain <code>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    Human,
+    // One JSON object per line
+    Json,
+    Sarif,
+}
+
+// Writes diagnostics to stderr in the chosen format. A SARIF log is a single document, so
+// its diagnostics are held until `finish`
+struct Reporter {
+    format: ErrorFormat,
+    color: bool,
+    pending: Vec<Diagnostic>,
+}
+
+impl Reporter {
+    fn new(format: ErrorFormat, color: bool) -> Self {
+        Reporter {
+            format,
+            color,
+            pending: vec![],
+        }
+    }
+
+    fn report(&mut self, source_map: &SourceMap, errors: &[LoxError]) {
+        for e in errors {
+            let diagnostic = e.diagnostic();
+            match self.format {
+                ErrorFormat::Human => eprint!("{}", diagnostic.render(source_map, self.color)),
+                ErrorFormat::Json => eprintln!("{}", diagnostic.to_json(source_map)),
+                ErrorFormat::Sarif => self.pending.push(diagnostic),
+            }
+        }
+    }
+
+    // The SARIF log is written even without diagnostics, so that dashboards see the
+    // previous ones as fixed
+    fn finish(&mut self, source_map: &SourceMap) {
+        if self.format == ErrorFormat::Sarif {
+            eprintln!("{}", sarif(&self.pending, source_map));
+            self.pending.clear();
+        }
+    }
+}
+
+// Prints the long description of an error code such as `E0101`
+fn explain(code: &str) -> Result<(), i32> {
+    if !is_error_code(code) {
+        eprintln!("Error: malformed error code '{code}', expected E followed by four digits");
+        return Err(EX_USAGE);
+    }
+    match ErrorCode::from_code(&code.to_uppercase()) {
+        Some(code) => {
+            print!("{}", code.explanation());
+            Ok(())
+        }
+        None => {
+            eprintln!("Error: no explanation for unknown error code '{code}'");
+            Err(EX_USAGE)
+        }
+    }
+}
+
+// Adds the source to the map and runs it. On failure returns the exit code matching the
+// phase that failed
+fn run(
+    interpreter: &mut Interpreter,
+    source_map: &mut SourceMap,
+    name: &str,
+    bytes: &[u8],
+    reporter: &mut Reporter,
+) -> Result<(), i32> {
+    // Invalid UTF-8 is reported along with the other lexical errors
+    let (source, lexical_errors) = decode_source(bytes, source_map.next_file_id());
+    let file_id = source_map.add_file(String::from(name), source.into_owned());
+    let source_map = &*source_map;
+    let source = match source_map.file(file_id) {
+        Some(file) => &file.source,
+        None => return Ok(()),
+    };
+    let scanner = Scanner::new_with_file(source, file_id);
+    // The parser pulls tokens from the scanner as it goes and keeps going after lexical
+    // errors, so that syntax errors are reported in the same run
+    let (statements, parse_errors) = Parser::new(scanner).parse();
+    let mut errors: Vec<LoxError> = lexical_errors
+        .into_iter()
+        .map(LoxError::from)
+        .chain(parse_errors)
+        .collect();
+    // Lexical errors come first, each phase in source order
+    errors.sort_by_key(|e| (!matches!(e, LoxError::Lexical(_)), e.span().start));
+    if !errors.is_empty() {
+        reporter.report(source_map, &errors);
+        return Err(EX_DATAERR);
+    }
+    if let Err(errors) = Resolver::new().resolve(&statements) {
+        let errors: Vec<LoxError> = errors.into_iter().map(LoxError::from).collect();
+        reporter.report(source_map, &errors);
+        return Err(EX_DATAERR);
+    }
+    if let Err(e) = interpreter.interpret(&statements) {
+        reporter.report(source_map, &[LoxError::from(e)]);
+        return Err(EX_SOFTWARE);
+    }
+
+    Ok(())
+}
+
+// `-` reads the script from stdin. Sources are read as bytes, invalid UTF-8 is reported
+// by `run`
+fn read_source(path: &str) -> io::Result<Vec<u8>> {
+    if path == "-" {
+        let mut source = vec![];
+        io::stdin().read_to_end(&mut source)?;
+        return Ok(source);
+    }
+
+    fs::read(path)
+}
+
+fn run_file(path: &str, reporter: &mut Reporter) -> Result<(), i32> {
+    let source = match read_source(path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Error: cannot read {path}: {e}");
+            return Err(EX_NOINPUT);
+        }
+    };
+    let name = if path == "-" { "<stdin>" } else { path };
+
+    let mut source_map = SourceMap::new();
+    let result = run(
+        &mut Interpreter::new(),
+        &mut source_map,
+        name,
+        &source,
+        reporter,
+    );
+    reporter.finish(&source_map);
+
+    result
+}
+
+fn run_prompt(reporter: &mut Reporter) {
+    let mut interpreter = Interpreter::new();
+    // Every line is kept since functions declared on it may still be called later
+    let mut source_map = SourceMap::new();
     loop {
-        let mut input = String::new();
+        let mut input = vec![];
         print!("> ");
         io::stdout().flush().unwrap();
-        io::stdin()
-            .read_line(&mut input)
+        let read = io::stdin()
+            .lock()
+            .read_until(b'\n', &mut input)
             .expect("Failed to read line");
-        println!("You entered: {}", input.trim());
+        if read == 0 {
+            break;
+        }
+        // Errors are already reported, the prompt keeps going
+        let _ = run(
+            &mut interpreter,
+            &mut source_map,
+            "<repl>",
+            &input,
+            reporter,
+        );
+        reporter.finish(&source_map);
     }
 }
 
+// `E` followed by four digits, in any case
+fn is_error_code(arg: &str) -> bool {
+    let mut chars = arg.chars();
+    matches!(chars.next(), Some('E' | 'e')) && arg.len() == 5 && chars.all(|c| c.is_ascii_digit())
+}
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    process::exit(EX_USAGE);
+}
+
+// The interpreter recurses on the native stack, up to `MAX_CALL_DEPTH` nested Lox calls,
+// so it runs on a thread with more stack than the main thread gets by default
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 2 {
-        println!("Usage: jlox [script]");
-    } else if args.len() == 2 {
-        // runFile(args[0]);
-    } else {
-        run_prompt();
+    let lox = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(lox_main);
+    match lox.map(|handle| handle.join()) {
+        Ok(Ok(())) => {}
+        // The panic message has already been printed
+        Ok(Err(_)) => process::exit(EX_SOFTWARE),
+        Err(e) => {
+            eprintln!("Error: cannot start the interpreter: {e}");
+            process::exit(EX_SOFTWARE);
+        }
+    }
+}
+
+fn lox_main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("explain") {
+        match args.as_slice() {
+            // A script named `explain` still runs when nothing follows it
+            [_] if Path::new("explain").is_file() => {}
+            [_, code] => {
+                if let Err(code) = explain(code) {
+                    process::exit(code);
+                }
+                return;
+            }
+            _ => usage(),
+        }
+    }
+
+    // Color is on by default only when diagnostics go to a terminal
+    let mut color = io::stderr().is_terminal();
+    let mut format = ErrorFormat::Human;
+    let mut path = None;
+    for arg in args {
+        match arg.as_str() {
+            "--color=auto" => color = io::stderr().is_terminal(),
+            "--color=always" => color = true,
+            "--color=never" => color = false,
+            "--error-format=human" => format = ErrorFormat::Human,
+            "--error-format=json" => format = ErrorFormat::Json,
+            "--error-format=sarif" => format = ErrorFormat::Sarif,
+            _ if arg.starts_with("--") => usage(),
+            _ if path.is_some() => usage(),
+            _ => path = Some(arg),
+        }
+    }
+
+    let mut reporter = Reporter::new(format, color);
+    match path {
+        Some(path) => {
+            if let Err(code) = run_file(&path, &mut reporter) {
+                process::exit(code);
+            }
+        }
+        None => run_prompt(&mut reporter),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        explain, is_error_code, run, run_file, ErrorFormat, Reporter, EX_DATAERR, EX_NOINPUT,
+        EX_SOFTWARE, EX_USAGE,
+    };
+    use lox::interpreter::Interpreter;
+    use lox::source_map::SourceMap;
+
+    #[test]
+    fn test_exit_codes() {
+        let mut interpreter = Interpreter::new();
+        let mut source_map = SourceMap::new();
+        // Held back by the SARIF reporter so that nothing is printed during the test
+        let mut reporter = Reporter::new(ErrorFormat::Sarif, false);
+        let mut run = |interpreter: &mut Interpreter, source: &str| {
+            run(
+                interpreter,
+                &mut source_map,
+                "test.lox",
+                source.as_bytes(),
+                &mut reporter,
+            )
+        };
+        assert_eq!(run(&mut interpreter, "var a = 1;\n"), Ok(()));
+        assert_eq!(run(&mut interpreter, "var a = @;\n"), Err(EX_DATAERR));
+        assert_eq!(run(&mut interpreter, "var a = ;\n"), Err(EX_DATAERR));
+        assert_eq!(run(&mut interpreter, "return a;\n"), Err(EX_DATAERR));
+        assert_eq!(run(&mut interpreter, "a = a + \"b\";\n"), Err(EX_SOFTWARE));
+        assert_eq!(
+            super::run(
+                &mut interpreter,
+                &mut SourceMap::new(),
+                "test.lox",
+                b"print \"\xFF\";\n",
+                &mut reporter
+            ),
+            Err(EX_DATAERR)
+        );
+        assert_eq!(reporter.pending.len(), 6);
+        assert_eq!(
+            run_file("does/not/exist.lox", &mut reporter),
+            Err(EX_NOINPUT)
+        );
+        assert_eq!(explain("E0101"), Ok(()));
+        assert_eq!(explain("e0401"), Ok(()));
+        assert_eq!(explain("E9999"), Err(EX_USAGE));
+        assert_eq!(explain("E01"), Err(EX_USAGE));
+        assert!(is_error_code("E0101") && is_error_code("e9999"));
+        assert!(!is_error_code("E010") && !is_error_code("E01a1") && !is_error_code("script"));
     }
 }
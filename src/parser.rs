@@ -0,0 +1,874 @@
+use crate::error_codes::ErrorCode;
+use crate::errors::{LexicalError, LoxError, ParseError};
+use crate::expr::{Depth, Expr};
+use crate::source_map::Span;
+use crate::stmt::{FunctionDecl, Stmt};
+use crate::tokens::{statement_keywords, Literal, Token, TokenType};
+use crate::utils::suggestion;
+use std::rc::Rc;
+
+const MAX_ARGUMENTS: usize = 255;
+
+// Pulls tokens from the scanner as it goes, so that only the current token and the previous
+// one are held instead of the whole token stream
+pub struct Parser<'src, I>
+where
+    I: Iterator<Item = Result<Token<'src>, LexicalError>>,
+{
+    tokens: I,
+    // One token of lookahead
+    current: Token<'src>,
+    previous: Token<'src>,
+    // Doc comments are kept apart from the tokens, along with the token that follows them
+    current_doc: Option<String>,
+    // Lexical errors met while pulling tokens and syntax errors, in source order
+    errors: Vec<LoxError>,
+}
+
+impl<'src, I> Parser<'src, I>
+where
+    I: Iterator<Item = Result<Token<'src>, LexicalError>>,
+{
+    pub fn new(tokens: I) -> Self {
+        let eof = Token::new(TokenType::Eof, "", 1, Span::default(), None);
+        let mut parser = Parser {
+            tokens,
+            current: eof.clone(),
+            previous: eof,
+            current_doc: None,
+            errors: vec![],
+        };
+        parser.current = parser.next_token();
+
+        parser
+    }
+
+    // Consume it
+    // Returns every statement that could be parsed along with every lexical and syntax
+    // error found
+    pub fn parse(mut self) -> (Vec<Stmt>, Vec<LoxError>) {
+        let mut statements = vec![];
+        while !self.is_at_end() {
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
+        }
+
+        (statements, self.errors)
+    }
+
+    // Panic mode recovery: a declaration with a syntax error is dropped and parsing
+    // restarts from the next statement boundary
+    fn declaration(&mut self) -> Option<Stmt> {
+        match self.parse_declaration() {
+            Ok(statement) => Some(statement),
+            Err(e) => {
+                self.errors.push(e.into());
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.is_at_end() {
+            if self.previous().token_type == TokenType::Semicolon {
+                return;
+            }
+
+            match self.peek().token_type {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return => return,
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    // declaration -> classDecl | funDecl | varDecl | statement
+    fn parse_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let doc = self.take_doc();
+        if self.match_types(&[TokenType::Class]) {
+            return self.class_declaration(doc);
+        }
+        if self.match_types(&[TokenType::Fun]) {
+            return Ok(Stmt::Function {
+                declaration: Rc::new(self.function("function", doc)?),
+            });
+        }
+        if self.match_types(&[TokenType::Var]) {
+            return self.var_declaration(doc);
+        }
+
+        self.statement()
+    }
+
+    // classDecl -> "class" IDENTIFIER ( "<" IDENTIFIER )? "{" function* "}"
+    fn class_declaration(&mut self, doc: Option<String>) -> Result<Stmt, ParseError> {
+        let name = self
+            .consume(TokenType::Identifier, "Expect class name")?
+            .clone()
+            .into_owned();
+        let superclass = if self.match_types(&[TokenType::Lt]) {
+            let superclass_name = self
+                .consume(TokenType::Identifier, "Expect superclass name")?
+                .clone()
+                .into_owned();
+            Some(Expr::Variable {
+                name: superclass_name,
+                depth: Depth::default(),
+            })
+        } else {
+            None
+        };
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body")?;
+
+        let mut methods = vec![];
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let doc = self.take_doc();
+            methods.push(Rc::new(self.function("method", doc)?));
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after class body")?;
+
+        Ok(Stmt::Class {
+            name,
+            superclass,
+            methods,
+            doc,
+        })
+    }
+
+    // function -> IDENTIFIER "(" parameters? ")" block
+    // parameters -> IDENTIFIER ( "," IDENTIFIER )*
+    fn function(&mut self, kind: &str, doc: Option<String>) -> Result<FunctionDecl, ParseError> {
+        let name = self
+            .consume(TokenType::Identifier, &format!("Expect {kind} name"))?
+            .clone()
+            .into_owned();
+        self.consume(
+            TokenType::LeftParen,
+            &format!("Expect '(' after {kind} name"),
+        )?;
+        let mut params = vec![];
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if params.len() >= MAX_ARGUMENTS {
+                    let e = ParseError::new(
+                        ErrorCode::TooManyArguments,
+                        format!("Can't have more than {MAX_ARGUMENTS} parameters"),
+                        self.peek(),
+                    );
+                    self.errors.push(e.into());
+                }
+                params.push(
+                    self.consume(TokenType::Identifier, "Expect parameter name")?
+                        .clone()
+                        .into_owned(),
+                );
+                if !self.match_types(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters")?;
+        self.consume(
+            TokenType::LeftBrace,
+            &format!("Expect '{{' before {kind} body"),
+        )?;
+        let body = self.block()?;
+
+        Ok(FunctionDecl {
+            name,
+            params,
+            body,
+            doc,
+        })
+    }
+
+    // varDecl -> "var" IDENTIFIER ( "=" expression )? ";"
+    fn var_declaration(&mut self, doc: Option<String>) -> Result<Stmt, ParseError> {
+        let name = self
+            .consume(TokenType::Identifier, "Expect variable name")?
+            .clone()
+            .into_owned();
+        let initializer = if self.match_types(&[TokenType::Eq]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(
+            TokenType::Semicolon,
+            "Expect ';' after variable declaration",
+        )?;
+
+        Ok(Stmt::Var {
+            name,
+            initializer,
+            doc,
+        })
+    }
+
+    // statement -> exprStmt | forStmt | ifStmt | printStmt | returnStmt | whileStmt | block
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        if self.match_types(&[TokenType::For]) {
+            return self.for_statement();
+        }
+        if self.match_types(&[TokenType::If]) {
+            return self.if_statement();
+        }
+        if self.match_types(&[TokenType::Print]) {
+            return self.print_statement();
+        }
+        if self.match_types(&[TokenType::Return]) {
+            return self.return_statement();
+        }
+        if self.match_types(&[TokenType::While]) {
+            return self.while_statement();
+        }
+        if self.match_types(&[TokenType::LeftBrace]) {
+            return Ok(Stmt::Block {
+                statements: self.block()?,
+            });
+        }
+
+        self.expression_statement()
+    }
+
+    // forStmt -> "for" "(" ( varDecl | exprStmt | ";" ) expression? ";" expression? ")" statement
+    // There is no dedicated node, the loop is desugared into a while loop
+    fn for_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'")?;
+        let initializer = if self.match_types(&[TokenType::Semicolon]) {
+            None
+        } else if self.match_types(&[TokenType::Var]) {
+            Some(self.var_declaration(None)?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+
+        let condition = if self.check(TokenType::Semicolon) {
+            Expr::Literal {
+                value: Literal::Bool { value: true },
+            }
+        } else {
+            self.expression()?
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after loop condition")?;
+
+        let increment = if self.check(TokenType::RightParen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::RightParen, "Expect ')' after for clauses")?;
+
+        let mut body = self.statement()?;
+        if let Some(increment) = increment {
+            body = Stmt::Block {
+                statements: vec![body, Stmt::Expression { expr: increment }],
+            };
+        }
+        body = Stmt::While {
+            condition,
+            body: Box::new(body),
+        };
+        if let Some(initializer) = initializer {
+            body = Stmt::Block {
+                statements: vec![initializer, body],
+            };
+        }
+
+        Ok(body)
+    }
+
+    // ifStmt -> "if" "(" expression ")" statement ( "else" statement )?
+    fn if_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'if'")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after if condition")?;
+
+        let then_branch = Box::new(self.statement()?);
+        let else_branch = if self.match_types(&[TokenType::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+
+        Ok(Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    // printStmt -> "print" expression ";"
+    fn print_statement(&mut self) -> Result<Stmt, ParseError> {
+        let expr = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after value")?;
+
+        Ok(Stmt::Print { expr })
+    }
+
+    // returnStmt -> "return" expression? ";"
+    fn return_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone().into_owned();
+        let value = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after return value")?;
+
+        Ok(Stmt::Return { keyword, value })
+    }
+
+    // whileStmt -> "while" "(" expression ")" statement
+    fn while_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after condition")?;
+        let body = Box::new(self.statement()?);
+
+        Ok(Stmt::While { condition, body })
+    }
+
+    // block -> "{" declaration* "}"
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut statements = vec![];
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after block")?;
+
+        Ok(statements)
+    }
+
+    // exprStmt -> expression ";"
+    fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
+        let expr = self.expression()?;
+        if let Err(e) = self.consume(TokenType::Semicolon, "Expect ';' after expression") {
+            return Err(match &expr {
+                Expr::Variable { name, .. } => suggest_keyword(e, name),
+                _ => e,
+            });
+        }
+
+        Ok(Stmt::Expression { expr })
+    }
+
+    // expression -> assignment
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        self.assignment()
+    }
+
+    // assignment -> ( call "." )? IDENTIFIER "=" assignment | logic_or
+    fn assignment(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.or()?;
+        if self.match_types(&[TokenType::Eq]) {
+            let equals = self.previous().clone().into_owned();
+            let value = self.assignment()?;
+            // The parser is not in a confused state, no need to synchronize
+            return match expr {
+                Expr::Variable { name, .. } => Ok(Expr::Assign {
+                    name,
+                    value: Box::new(value),
+                    depth: Depth::default(),
+                }),
+                Expr::Get { object, name } => Ok(Expr::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                }),
+                _ => {
+                    let e = ParseError::new(
+                        ErrorCode::InvalidAssignmentTarget,
+                        String::from("Invalid assignment target"),
+                        &equals,
+                    );
+                    self.errors.push(e.into());
+                    Ok(expr)
+                }
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // logic_or -> logic_and ( "or" logic_and )*
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.match_types(&[TokenType::Or]) {
+            let operator = self.previous().clone().into_owned();
+            let right = self.and()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // logic_and -> equality ( "and" equality )*
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.equality()?;
+        while self.match_types(&[TokenType::And]) {
+            let operator = self.previous().clone().into_owned();
+            let right = self.equality()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // equality -> comparison ( ( "!=" | "==" ) comparison )*
+    fn equality(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.comparison()?;
+        while self.match_types(&[TokenType::BangEq, TokenType::EqEq]) {
+            let operator = self.previous().clone().into_owned();
+            let right = self.comparison()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // comparison -> term ( ( ">" | ">=" | "<" | "<=" ) term )*
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term()?;
+        while self.match_types(&[TokenType::Gt, TokenType::Gte, TokenType::Lt, TokenType::Lte]) {
+            let operator = self.previous().clone().into_owned();
+            let right = self.term()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // term -> factor ( ( "-" | "+" ) factor )*
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.factor()?;
+        while self.match_types(&[TokenType::Minus, TokenType::Plus]) {
+            let operator = self.previous().clone().into_owned();
+            let right = self.factor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // factor -> unary ( ( "/" | "*" ) unary )*
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        while self.match_types(&[TokenType::Slash, TokenType::Star]) {
+            let operator = self.previous().clone().into_owned();
+            let right = self.unary()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // unary -> ( "!" | "-" ) unary | call
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.match_types(&[TokenType::Bang, TokenType::Minus]) {
+            let operator = self.previous().clone().into_owned();
+            let right = self.unary()?;
+            return Ok(Expr::Unary {
+                operator,
+                right: Box::new(right),
+            });
+        }
+
+        self.call()
+    }
+
+    // call -> primary ( "(" arguments? ")" | "." IDENTIFIER )*
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.match_types(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_types(&[TokenType::Dot]) {
+                let name = self
+                    .consume(TokenType::Identifier, "Expect property name after '.'")?
+                    .clone()
+                    .into_owned();
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    // arguments -> expression ( "," expression )*
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let mut arguments = vec![];
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if arguments.len() >= MAX_ARGUMENTS {
+                    let e = ParseError::new(
+                        ErrorCode::TooManyArguments,
+                        format!("Can't have more than {MAX_ARGUMENTS} arguments"),
+                        self.peek(),
+                    );
+                    self.errors.push(e.into());
+                }
+                arguments.push(self.expression()?);
+                if !self.match_types(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self
+            .consume(TokenType::RightParen, "Expect ')' after arguments")?
+            .clone()
+            .into_owned();
+
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        })
+    }
+
+    // primary -> NUMBER | STRING | IDENTIFIER | "true" | "false" | "nil" | "this"
+    //          | "(" expression ")" | "super" "." IDENTIFIER
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        if self.match_types(&[TokenType::False]) {
+            return Ok(Expr::Literal {
+                value: Literal::Bool { value: false },
+            });
+        }
+        if self.match_types(&[TokenType::True]) {
+            return Ok(Expr::Literal {
+                value: Literal::Bool { value: true },
+            });
+        }
+        if self.match_types(&[TokenType::Nil]) {
+            return Ok(Expr::Literal {
+                value: Literal::Nil,
+            });
+        }
+        if self.match_types(&[TokenType::Number, TokenType::String]) {
+            let value = match &self.previous().literal {
+                Some(literal) => literal.clone().into_owned(),
+                None => Literal::Nil,
+            };
+            return Ok(Expr::Literal { value });
+        }
+        if self.match_types(&[TokenType::This]) {
+            return Ok(Expr::This {
+                keyword: self.previous().clone().into_owned(),
+                depth: Depth::default(),
+            });
+        }
+        if self.match_types(&[TokenType::Super]) {
+            let keyword = self.previous().clone().into_owned();
+            self.consume(TokenType::Dot, "Expect '.' after 'super'")?;
+            let method = self
+                .consume(TokenType::Identifier, "Expect superclass method name")?
+                .clone()
+                .into_owned();
+            return Ok(Expr::Super {
+                keyword,
+                method,
+                depth: Depth::default(),
+            });
+        }
+        if self.match_types(&[TokenType::Identifier]) {
+            return Ok(Expr::Variable {
+                name: self.previous().clone().into_owned(),
+                depth: Depth::default(),
+            });
+        }
+        if self.match_types(&[TokenType::LeftParen]) {
+            let expr = self.expression()?;
+            self.consume(TokenType::RightParen, "Expect ')' after expression")?;
+            return Ok(Expr::Grouping {
+                expr: Box::new(expr),
+            });
+        }
+
+        Err(ParseError::new(
+            ErrorCode::ExpectedExpression,
+            String::from("Expect expression"),
+            self.peek(),
+        ))
+    }
+
+    // Doc comment of the declaration starting at the current token
+    fn take_doc(&mut self) -> Option<String> {
+        self.current_doc.take()
+    }
+
+    fn match_types(&mut self, types: &[TokenType]) -> bool {
+        for t in types {
+            if self.check(*t) {
+                self.advance();
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn consume(
+        &mut self,
+        token_type: TokenType,
+        message: &str,
+    ) -> Result<&Token<'src>, ParseError> {
+        if self.check(token_type) {
+            return Ok(self.advance());
+        }
+
+        Err(ParseError::new(
+            ErrorCode::ExpectedToken,
+            String::from(message),
+            self.peek(),
+        ))
+    }
+
+    fn check(&self, token_type: TokenType) -> bool {
+        !self.is_at_end() && self.peek().token_type == token_type
+    }
+
+    fn advance(&mut self) -> &Token<'src> {
+        if !self.is_at_end() {
+            let next = self.next_token();
+            self.previous = std::mem::replace(&mut self.current, next);
+        }
+        self.previous()
+    }
+
+    // Next token of the stream, doc comments and lexical errors are set aside on the way
+    fn next_token(&mut self) -> Token<'src> {
+        let mut doc: Option<String> = None;
+        loop {
+            match self.tokens.next() {
+                Some(Ok(token)) if token.token_type == TokenType::DocComment => {
+                    let text = doc_text(&token.lexeme);
+                    match doc.as_mut() {
+                        Some(doc) => {
+                            doc.push('\n');
+                            doc.push_str(text);
+                        }
+                        None => doc = Some(String::from(text)),
+                    }
+                }
+                Some(Ok(token)) => {
+                    self.current_doc = doc;
+                    return token;
+                }
+                Some(Err(e)) => self.errors.push(e.into()),
+                // The scanner always ends with Eof, other streams get one where they stop
+                None => {
+                    let end = self.current.span.end;
+                    let span = Span::new(end, end, self.current.span.file_id);
+                    return Token::new(TokenType::Eof, "", self.current.line, span, None);
+                }
+            }
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.peek().token_type == TokenType::Eof
+    }
+
+    fn peek(&self) -> &Token<'src> {
+        &self.current
+    }
+
+    fn previous(&self) -> &Token<'src> {
+        &self.previous
+    }
+}
+
+// A lone identifier followed by a token that cannot continue the expression, such as
+// `fucn f() {}` or `retrun x;`, is likely a misspelled keyword
+fn suggest_keyword(error: ParseError, name: &Token) -> ParseError {
+    match suggestion(&name.lexeme, statement_keywords()) {
+        Some(help) => error
+            .with_label(name.span, "not a keyword")
+            .with_help(Some(help)),
+        None => error,
+    }
+}
+
+// `/// text` gives `text`
+fn doc_text(lexeme: &str) -> &str {
+    let text = lexeme.strip_prefix("///").unwrap_or(lexeme);
+    text.strip_prefix(' ').unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Parser;
+    use crate::ast::AstPrinter;
+    use crate::stmt::Stmt;
+    use crate::token_scanner::Scanner;
+
+    fn parse_errors(source: &str) -> Vec<String> {
+        let (_, errors) = Parser::new(Scanner::new(source)).parse();
+        errors.iter().map(|e| e.to_string()).collect()
+    }
+
+    fn parse(source: &str) -> String {
+        let (mut statements, errors) = Parser::new(Scanner::new(source)).parse();
+        assert!(errors.is_empty());
+        match statements.remove(0) {
+            Stmt::Expression { expr } => AstPrinter::print(expr),
+            _ => panic!("Expected an expression statement"),
+        }
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(parse("1 + 2 * 3 == 7;"), "(== (+ 1 (* 2 3)) 7)");
+        assert_eq!(
+            parse("-1 - -2 / (3 - 4);"),
+            "(- (- 1) (/ (- 2) (group (- 3 4))))"
+        );
+        assert_eq!(parse("!true == false;"), "(== (! true) false)");
+        assert_eq!(parse("1 < 2 == nil;"), "(== (< 1 2) nil)");
+    }
+
+    #[test]
+    fn test_assignment() {
+        assert_eq!(
+            parse("a = b = 1 or 2 and c;"),
+            "(= a (= b (or 1 (and 2 c))))"
+        );
+
+        assert_eq!(
+            parse_errors("1 + a = 2;"),
+            vec!["Error: Invalid assignment target. Line 1, position 6..7"]
+        );
+    }
+
+    #[test]
+    fn test_call() {
+        assert_eq!(parse("f(1, g(2))(3);"), "(call (call f 1 (call g 2)) 3)");
+        assert_eq!(
+            parse("this.a.b = super.c(1);"),
+            "(= (. (. this a) b) (call (. super c) 1))"
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            parse_errors("(1 + 2;"),
+            vec!["Error: Expect ')' after expression. Line 1, position 6..7"]
+        );
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let source = "var a = ;\nprint a;\nfun f( { return 1; }\nclass A { m() { var = 2; print 3; } }\nprint 4 5;\nprint 6;\n";
+        assert_eq!(
+            parse_errors(source),
+            vec![
+                "Error: Expect expression. Line 1, position 8..9",
+                "Error: Expect parameter name. Line 3, position 26..27",
+                "Error: Expect expression. Line 3, position 38..39",
+                "Error: Expect variable name. Line 4, position 60..61",
+                "Error: Expect ';' after value. Line 5, position 86..87",
+            ]
+        );
+
+        let (statements, _) = Parser::new(Scanner::new(source)).parse();
+        // `print a;`, the `return 1;` left after `fun f(`, `class A` and `print 6;`
+        assert_eq!(statements.len(), 4);
+    }
+
+    #[test]
+    fn test_keyword_suggestions() {
+        // Span the hint is anchored to, and the hint
+        let suggestions = |source: &str| -> Vec<String> {
+            let (_, errors) = Parser::new(Scanner::new(source)).parse();
+            errors
+                .iter()
+                .map(|e| e.diagnostic())
+                .filter_map(|d| Some(format!("{} {}", d.labels.first()?.span, d.help?)))
+                .collect()
+        };
+        assert_eq!(suggestions("fucn f() {}"), vec!["0..4 did you mean 'fun'?"]);
+        assert_eq!(
+            suggestions("fun f(a) { retrun a; }"),
+            vec!["11..17 did you mean 'return'?"]
+        );
+        assert_eq!(suggestions("clas A {}"), vec!["0..4 did you mean 'class'?"]);
+        // Only a lone identifier is a keyword candidate, mistakes later in the statement
+        // are not
+        assert!(suggestions("var i = 0;\ni = i + 1\nprint i;").is_empty());
+        assert!(suggestions("fun foo(a) {}\nfoo(1;").is_empty());
+        assert!(suggestions("whlie (true) print 1;").is_empty());
+        // Not a statement keyword, and too far from any
+        assert!(suggestions("ture 1;").is_empty());
+        assert!(suggestions("counter 1;").is_empty());
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let source = "/// Answer\n/// to everything\nvar answer = 42;\n\n/// Shape\nclass Shape {\n  /// Area of it\n  area() { return 0; }\n  perimeter() { return 0; }\n}\n//// Not a doc\nfun f(a) {\n  /// Ignored before a statement\n  print a;\n}\n";
+        let (statements, errors) = Parser::new(Scanner::new(source)).parse();
+        assert!(errors.is_empty());
+
+        match &statements[0] {
+            Stmt::Var { doc, .. } => assert_eq!(doc.as_deref(), Some("Answer\nto everything")),
+            _ => panic!("Expected a variable declaration"),
+        }
+        match &statements[1] {
+            Stmt::Class { doc, methods, .. } => {
+                assert_eq!(doc.as_deref(), Some("Shape"));
+                assert_eq!(methods[0].doc.as_deref(), Some("Area of it"));
+                assert_eq!(methods[1].doc, None);
+            }
+            _ => panic!("Expected a class declaration"),
+        }
+        match &statements[2] {
+            Stmt::Function { declaration } => {
+                assert_eq!(declaration.doc, None);
+                assert_eq!(declaration.body.len(), 1);
+            }
+            _ => panic!("Expected a function declaration"),
+        }
+    }
+}
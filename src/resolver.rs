@@ -0,0 +1,357 @@
+use crate::error_codes::ErrorCode;
+use crate::errors::ResolveError;
+use crate::expr::{Depth, Expr};
+use crate::stmt::{FunctionDecl, Stmt};
+use crate::tokens::Token;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+// Static pass run between parsing and execution. It stores in every variable expression
+// the number of scopes to walk up to find its declaration, and reports semantic errors
+pub struct Resolver {
+    // Each scope maps a name to whether its initializer has been resolved yet
+    scopes: Vec<HashMap<String, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    errors: Vec<ResolveError>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Resolver {
+            scopes: vec![],
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            errors: vec![],
+        }
+    }
+
+    // Consume it
+    pub fn resolve(mut self, statements: &[Stmt]) -> Result<(), Vec<ResolveError>> {
+        self.resolve_statements(statements);
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+
+        Ok(())
+    }
+
+    fn resolve_statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+    }
+
+    fn resolve_statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression { expr } | Stmt::Print { expr } => self.resolve_expression(expr),
+            Stmt::Var {
+                name, initializer, ..
+            } => {
+                self.declare(name);
+                if let Some(initializer) = initializer {
+                    self.resolve_expression(initializer);
+                }
+                self.define(name);
+            }
+            Stmt::Function { declaration } => {
+                // Defined before the body is resolved so the function can recurse
+                self.declare(&declaration.name);
+                self.define(&declaration.name);
+                self.resolve_function(declaration, FunctionType::Function);
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Class;
+                self.declare(name);
+                self.define(name);
+
+                if let Some(superclass) = superclass {
+                    if let Expr::Variable {
+                        name: superclass_name,
+                        ..
+                    } = superclass
+                    {
+                        if superclass_name.lexeme == name.lexeme {
+                            self.error(
+                                ErrorCode::SelfInheritance,
+                                "A class can't inherit from itself",
+                                superclass_name,
+                            );
+                        }
+                    }
+                    self.current_class = ClassType::Subclass;
+                    self.resolve_expression(superclass);
+
+                    self.begin_scope();
+                    self.define_name("super");
+                }
+
+                self.begin_scope();
+                self.define_name("this");
+                for method in methods {
+                    let function_type = if method.name.lexeme == "init" {
+                        FunctionType::Initializer
+                    } else {
+                        FunctionType::Method
+                    };
+                    self.resolve_function(method, function_type);
+                }
+                self.end_scope();
+
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+                self.current_class = enclosing_class;
+            }
+            Stmt::Return { keyword, value } => {
+                if self.current_function == FunctionType::None {
+                    self.error(
+                        ErrorCode::TopLevelReturn,
+                        "Can't return from top-level code",
+                        keyword,
+                    );
+                }
+                if let Some(value) = value {
+                    if self.current_function == FunctionType::Initializer {
+                        self.error(
+                            ErrorCode::ReturnValueFromInitializer,
+                            "Can't return a value from an initializer",
+                            keyword,
+                        );
+                    }
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Block { statements } => {
+                self.begin_scope();
+                self.resolve_statements(statements);
+                self.end_scope();
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expression(condition);
+                self.resolve_statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_statement(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.resolve_expression(condition);
+                self.resolve_statement(body);
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, declaration: &FunctionDecl, function_type: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = function_type;
+
+        self.begin_scope();
+        for param in &declaration.params {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve_statements(&declaration.body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+    }
+
+    fn resolve_expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable { name, depth } => {
+                if let Some(false) = self.scopes.last().and_then(|s| s.get(name.lexeme.as_ref())) {
+                    self.error(
+                        ErrorCode::LocalInOwnInitializer,
+                        "Can't read local variable in its own initializer",
+                        name,
+                    );
+                }
+                self.resolve_local(name, depth);
+            }
+            Expr::Assign { name, value, depth } => {
+                self.resolve_expression(value);
+                self.resolve_local(name, depth);
+            }
+            Expr::This { keyword, depth } => {
+                if self.current_class == ClassType::None {
+                    self.error(
+                        ErrorCode::ThisOutsideClass,
+                        "Can't use 'this' outside of a class",
+                        keyword,
+                    );
+                    return;
+                }
+                self.resolve_local(keyword, depth);
+            }
+            Expr::Super { keyword, depth, .. } => {
+                match self.current_class {
+                    ClassType::None => {
+                        self.error(
+                            ErrorCode::SuperOutsideClass,
+                            "Can't use 'super' outside of a class",
+                            keyword,
+                        );
+                    }
+                    ClassType::Class => {
+                        self.error(
+                            ErrorCode::SuperWithoutSuperclass,
+                            "Can't use 'super' in a class with no superclass",
+                            keyword,
+                        );
+                    }
+                    ClassType::Subclass => {}
+                }
+                self.resolve_local(keyword, depth);
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expr::Unary { right, .. } => self.resolve_expression(right),
+            Expr::Grouping { expr } => self.resolve_expression(expr),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.resolve_expression(callee);
+                for argument in arguments {
+                    self.resolve_expression(argument);
+                }
+            }
+            Expr::Get { object, .. } => self.resolve_expression(object),
+            Expr::Set { object, value, .. } => {
+                self.resolve_expression(value);
+                self.resolve_expression(object);
+            }
+            Expr::Literal { .. } => {}
+        }
+    }
+
+    // Variables not found in any scope are assumed to be globals and keep no depth
+    fn resolve_local(&mut self, name: &Token, depth: &Depth) {
+        for (distance, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(name.lexeme.as_ref()) {
+                depth.set(Some(distance));
+                return;
+            }
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let already_declared = match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.lexeme.to_string(), false).is_some(),
+            None => false,
+        };
+        if already_declared {
+            self.error(
+                ErrorCode::DuplicateVariable,
+                "Already a variable with this name in this scope",
+                name,
+            );
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        self.define_name(name.lexeme.as_ref());
+    }
+
+    fn define_name(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(String::from(name), true);
+        }
+    }
+
+    fn error(&mut self, code: ErrorCode, message: &str, token: &Token) {
+        self.errors
+            .push(ResolveError::new(code, String::from(message), token));
+    }
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Resolver;
+    use crate::parser::Parser;
+    use crate::token_scanner::Scanner;
+
+    fn resolve(source: &str) -> Vec<String> {
+        let (statements, errors) = Parser::new(Scanner::new(source)).parse();
+        assert!(errors.is_empty());
+        match Resolver::new().resolve(&statements) {
+            Ok(()) => vec![],
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_valid_program() {
+        let program = "var a = 1;\nfun f(b) { var c = a + b; return c; }\nclass A { init() { this.a = 1; } }\nclass B < A { init() { super.init(); return; } }\n";
+        assert!(resolve(program).is_empty());
+    }
+
+    #[test]
+    fn test_semantic_errors() {
+        assert_eq!(
+            resolve("{ var a = a; }"),
+            vec![
+                "Error: Can't read local variable in its own initializer. Line 1, position 10..11"
+            ]
+        );
+        assert_eq!(
+            resolve("fun f() { var a; var a; }"),
+            vec!["Error: Already a variable with this name in this scope. Line 1, position 21..22"]
+        );
+        assert_eq!(
+            resolve("return 1;"),
+            vec!["Error: Can't return from top-level code. Line 1, position 0..6"]
+        );
+        assert_eq!(
+            resolve("print this;"),
+            vec!["Error: Can't use 'this' outside of a class. Line 1, position 6..10"]
+        );
+        assert_eq!(
+            resolve("class A { f() { super.f(); } }"),
+            vec!["Error: Can't use 'super' in a class with no superclass. Line 1, position 16..21"]
+        );
+        assert_eq!(
+            resolve("class A { init() { return 1; } }"),
+            vec!["Error: Can't return a value from an initializer. Line 1, position 19..25"]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FileId(pub usize);
+
+// Half-open range of byte offsets into the source of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub file_id: FileId,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, file_id: FileId) -> Self {
+        Span {
+            start,
+            end,
+            file_id,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+    // Byte offset at which each line starts, the first one is always 0
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(name: String, source: String) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+
+        SourceFile {
+            name,
+            source,
+            line_starts,
+        }
+    }
+
+    // 1-based line and column of a byte offset. Columns count characters, not bytes,
+    // and offsets past the end of the file are clamped to it
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line_index = match self.line_starts.binary_search(&offset) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+        let line_start = self.line_starts[line_index];
+        let column = match self.source.get(line_start..offset) {
+            Some(prefix) => prefix.chars().count(),
+            None => offset - line_start,
+        };
+
+        (line_index + 1, column + 1)
+    }
+
+    // Text of a 1-based line, without its line terminator
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = match self.line_starts.get(line) {
+            Some(next) => *next,
+            None => self.source.len(),
+        };
+        let text = self.source.get(start..end)?;
+
+        Some(text.trim_end_matches(['\n', '\r']))
+    }
+}
+
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap { files: vec![] }
+    }
+
+    // Id that the next added file will get
+    pub fn next_file_id(&self) -> FileId {
+        FileId(self.files.len())
+    }
+
+    pub fn add_file(&mut self, name: String, source: String) -> FileId {
+        self.files.push(SourceFile::new(name, source));
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn file(&self, file_id: FileId) -> Option<&SourceFile> {
+        self.files.get(file_id.0)
+    }
+
+    pub fn line_col(&self, file_id: FileId, offset: usize) -> Option<(usize, usize)> {
+        self.file(file_id).map(|file| file.line_col(offset))
+    }
+
+    pub fn line_text(&self, file_id: FileId, line: usize) -> Option<&str> {
+        self.file(file_id)?.line_text(line)
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        SourceMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SourceMap, Span};
+
+    #[test]
+    fn test_line_col() {
+        let mut source_map = SourceMap::new();
+        let id = source_map.add_file(
+            String::from("test.lox"),
+            String::from("var a = 1;\r\nprint \"é\" + a;\n"),
+        );
+
+        assert_eq!(source_map.line_col(id, 0), Some((1, 1)));
+        assert_eq!(source_map.line_col(id, 4), Some((1, 5)));
+        assert_eq!(source_map.line_col(id, 12), Some((2, 1)));
+        // `é` takes two bytes but a single column
+        assert_eq!(source_map.line_col(id, 22), Some((2, 10)));
+        assert_eq!(source_map.line_col(id, 100), Some((3, 1)));
+
+        assert_eq!(source_map.line_text(id, 1), Some("var a = 1;"));
+        assert_eq!(source_map.line_text(id, 2), Some("print \"é\" + a;"));
+        assert_eq!(source_map.line_text(id, 3), Some(""));
+        assert_eq!(source_map.line_text(id, 4), None);
+        assert_eq!(source_map.line_text(id, 0), None);
+
+        let span = Span::new(6, 10, id);
+        assert_eq!(span.to_string(), "6..10");
+    }
+}
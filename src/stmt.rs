@@ -0,0 +1,51 @@
+use crate::expr::Expr;
+use crate::tokens::Token;
+use std::rc::Rc;
+
+// Shared between the AST and every closure created from the declaration
+pub struct FunctionDecl {
+    pub name: Token<'static>,
+    pub params: Vec<Token<'static>>,
+    pub body: Vec<Stmt>,
+    // Text of the `///` comments right before the declaration, one line per comment
+    pub doc: Option<String>,
+}
+
+pub enum Stmt {
+    Expression {
+        expr: Expr,
+    },
+    Print {
+        expr: Expr,
+    },
+    Var {
+        name: Token<'static>,
+        initializer: Option<Expr>,
+        doc: Option<String>,
+    },
+    Function {
+        declaration: Rc<FunctionDecl>,
+    },
+    Class {
+        name: Token<'static>,
+        superclass: Option<Expr>,
+        methods: Vec<Rc<FunctionDecl>>,
+        doc: Option<String>,
+    },
+    Return {
+        keyword: Token<'static>,
+        value: Option<Expr>,
+    },
+    Block {
+        statements: Vec<Stmt>,
+    },
+    If {
+        condition: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    While {
+        condition: Expr,
+        body: Box<Stmt>,
+    },
+}
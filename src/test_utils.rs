@@ -0,0 +1,60 @@
+// Helpers shared by the tests of several modules
+
+// Small xorshift generator so that random tests are reproducible without dependencies
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // In `0..bound`
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    pub fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+// Fragments that exercise every branch of the scanner, valid or not
+pub const FRAGMENTS: &[&str] = &[
+    " ", "  ", "\t", "\r", "\n", "\r\n", "//", "/*", "*/", "/", "*", "\"", "\\", "\\n", "\\u{",
+    "}", "{", "(", ")", ";", ".", ",", "+", "-", "=", "!", "<", ">", "0", "7", "0x", "0b", "_",
+    "e", "E", "1.5", "var", "print", "fun", "x", "é", "👋", "@", "#",
+];
+
+// Between 1 and `max_fragments` fragments picked at random
+pub fn random_source(random: &mut Random, fragments: &[&str], max_fragments: usize) -> String {
+    let length = 1 + random.below(max_fragments);
+    (0..length).map(|_| random.pick(fragments)).collect()
+}
+
+// Any char, mostly ASCII so that tokens still form between the unusual ones
+pub fn random_char(random: &mut Random) -> char {
+    match random.below(4) {
+        0 => char::from_u32(random.below(0x11_0000) as u32).unwrap_or('\u{FFFD}'),
+        _ => random.below(0x80) as u8 as char,
+    }
+}
+
+// Fragments mixed with arbitrary chars
+pub fn random_sources(count: usize) -> Vec<String> {
+    let mut random = Random(0x9E37_79B9_7F4A_7C15);
+    (0..count)
+        .map(|_| {
+            let mut source = String::new();
+            for _ in 0..1 + random.below(40) {
+                match random.below(4) {
+                    0 => source.push(random_char(&mut random)),
+                    _ => source.push_str(random.pick(FRAGMENTS)),
+                }
+            }
+            source
+        })
+        .collect()
+}
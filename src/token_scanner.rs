@@ -1,326 +1,685 @@
+use crate::error_codes::ErrorCode;
 use crate::errors::LexicalError;
-use crate::tokens::{Literal, Token, TokenType};
-use crate::utils::{is_alpha, is_alphanumeric};
-use std::str;
+use crate::source_map::{FileId, Span};
+use crate::tokens::{match_punctuator, Literal, Token, TokenType, Trivia, TriviaKind};
+use crate::utils::{is_identifier_continue, is_identifier_start};
+use std::borrow::Cow;
+use std::collections::VecDeque;
 
-struct Scanner<'a> {
-    source: &'a [u8],
+// Positions are byte offsets into the source, always on a char boundary
+pub struct Scanner<'a> {
+    source: &'a str,
+    file_id: FileId,
     current_line: usize,
     current_position: usize,
+    // Items scanned but not yet handed out by `next`
+    pending: VecDeque<Result<Token<'a>, LexicalError>>,
+    // Set once the Eof token has been produced
+    finished: bool,
+    keep_trivia: bool,
+    // Trivia scanned since the last token, it will lead the next one
+    leading_trivia: Vec<Trivia<'a>>,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(source: &'a [u8]) -> Result<Self, &'static str> {
-        if source.is_empty() {
-            return Err("Cannot create a Scanner with empty source");
-        }
+    pub fn new(source: &'a str) -> Self {
+        Scanner::new_with_file(source, FileId::default())
+    }
 
-        Ok(Scanner {
-            source: source,
+    // Spans of tokens and errors will refer to the given file of the SourceMap
+    pub fn new_with_file(source: &'a str, file_id: FileId) -> Self {
+        Scanner {
+            source,
+            file_id,
             current_line: 1,
             current_position: 0,
-        })
+            pending: VecDeque::new(),
+            finished: false,
+            keep_trivia: false,
+            leading_trivia: vec![],
+        }
+    }
+
+    // Attach whitespace and comments to the tokens. Concatenating the leading trivia, the
+    // lexeme and the trailing trivia of every token gives back the source
+    pub fn with_trivia(mut self) -> Self {
+        self.keep_trivia = true;
+        self
+    }
+
+    // Resume scanning from a token boundary of a previous scan, see `incremental::relex`
+    pub(crate) fn resume_at(mut self, position: usize, line: usize) -> Self {
+        self.current_position = position;
+        self.current_line = line;
+        self
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(start, end, self.file_id)
+    }
+
+    fn char_at(&self, position: usize) -> Option<char> {
+        self.source.get(position..)?.chars().next()
+    }
+
+    // Consume it
+    // Stops at the first lexical error
+    pub fn scan(self) -> Result<Vec<Token<'a>>, LexicalError> {
+        self.collect()
     }
 
     // Consume it
-    pub fn scan(mut self) -> Result<Vec<Token>, LexicalError> {
+    // Invalid input is skipped so that every lexical error of the source is reported at once
+    pub fn scan_all(self) -> (Vec<Token<'a>>, Vec<LexicalError>) {
         let mut tokens = vec![];
-        while self.current_position < self.source.len() {
-            match self.source[self.current_position] as char {
-                '(' => tokens.push(Token::new(
-                    TokenType::LeftParen,
-                    String::from("("),
-                    self.current_line,
-                    self.current_position,
-                    self.current_position,
-                    None,
-                )),
-                ')' => tokens.push(Token::new(
-                    TokenType::RightParen,
-                    String::from(")"),
-                    self.current_line,
-                    self.current_position,
-                    self.current_position,
-                    None,
-                )),
-                '{' => tokens.push(Token::new(
-                    TokenType::LeftBrace,
-                    String::from("{"),
-                    self.current_line,
-                    self.current_position,
-                    self.current_position,
-                    None,
-                )),
-                '}' => tokens.push(Token::new(
-                    TokenType::RightBrace,
-                    String::from("{"),
+        let mut errors = vec![];
+        for item in self {
+            match item {
+                Ok(token) => tokens.push(token),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        (tokens, errors)
+    }
+
+    fn add_token(&mut self, token: Token<'a>) {
+        self.pending.push_back(Ok(token));
+    }
+
+    fn add_error(&mut self, error: LexicalError) {
+        self.pending.push_back(Err(error));
+    }
+
+    // Scans the lexeme starting with `c`, which may produce no item at all for whitespace
+    // and comments, or several of them for a string with invalid escape sequences
+    fn scan_token(&mut self, c: char) {
+        match c {
+            '/' if self.is_doc_comment() => {
+                // Doc comment, kept as a token so that the parser can attach it
+                let start_position = self.current_position;
+                while let Some(cur_char) = self.char_at(self.current_position) {
+                    if cur_char == '\n' {
+                        break;
+                    }
+                    self.current_position += cur_char.len_utf8();
+                }
+                self.add_token(Token::new(
+                    TokenType::DocComment,
+                    &self.source[start_position..self.current_position],
                     self.current_line,
-                    self.current_position,
-                    self.current_position,
+                    self.span(start_position, self.current_position),
                     None,
-                )),
-                '!' => {
-                    if self.current_position + 1 < self.source.len()
-                        && self.source[self.current_position + 1] as char == '='
-                    {
-                        tokens.push(Token::new(
-                            TokenType::BangEq,
-                            String::from("!="),
-                            self.current_line,
-                            self.current_position,
-                            self.current_position + 1,
-                            None,
-                        ))
-                    } else {
-                        tokens.push(Token::new(
-                            TokenType::Bang,
-                            String::from("!"),
-                            self.current_line,
-                            self.current_position,
-                            self.current_position,
-                            None,
-                        ))
+                ));
+                return;
+            }
+            '/' if self.char_at(self.current_position + 1) == Some('/') => {
+                // Comment, consume everything untill the end of the line, which is
+                // scanned on its own
+                while let Some(cur_char) = self.char_at(self.current_position) {
+                    if cur_char == '\n' {
+                        break;
                     }
+                    self.current_position += cur_char.len_utf8();
                 }
-                '=' => {
-                    if self.current_position + 1 < self.source.len()
-                        && self.source[self.current_position + 1] as char == '='
-                    {
-                        tokens.push(Token::new(
-                            TokenType::EqEq,
-                            String::from("=="),
-                            self.current_line,
-                            self.current_position,
-                            self.current_position + 1,
-                            None,
-                        ));
-                        self.current_position += 1;
-                    } else {
-                        tokens.push(Token::new(
-                            TokenType::Eq,
-                            String::from("="),
-                            self.current_line,
-                            self.current_position,
-                            self.current_position,
-                            None,
-                        ))
-                    }
+                return;
+            }
+            '/' if self.char_at(self.current_position + 1) == Some('*') => {
+                if let Err(e) = self.scan_block_comment() {
+                    self.add_error(e);
                 }
-                '<' => {
-                    if self.current_position + 1 < self.source.len()
-                        && self.source[self.current_position + 1] as char == '='
-                    {
-                        tokens.push(Token::new(
-                            TokenType::Lte,
-                            String::from("<="),
-                            self.current_line,
-                            self.current_position,
-                            self.current_position + 1,
-                            None,
-                        ))
-                    } else {
-                        tokens.push(Token::new(
-                            TokenType::Lt,
-                            String::from("<"),
-                            self.current_line,
-                            self.current_position,
-                            self.current_position,
-                            None,
-                        ))
+                return;
+            }
+            '"' => {
+                // String literal, escape sequences are decoded into its value
+                let start_position = self.current_position;
+                let start_line = self.current_line;
+                // Only allocated once an escape sequence is found, the value is a slice
+                // of the source otherwise
+                let mut decoded: Option<String> = None;
+                self.current_position += 1;
+                loop {
+                    match self.char_at(self.current_position) {
+                        Some('"') => break,
+                        Some('\\') => {
+                            let value = decoded.get_or_insert_with(|| {
+                                String::from(
+                                    &self.source[start_position + 1..self.current_position],
+                                )
+                            });
+                            match self.scan_escape() {
+                                Ok(escaped) => value.push(escaped),
+                                Err(e) => self.add_error(e),
+                            }
+                        }
+                        Some(cur_char) => {
+                            if cur_char == '\n' {
+                                self.current_line += 1;
+                            }
+                            if let Some(value) = decoded.as_mut() {
+                                value.push(cur_char);
+                            }
+                            self.current_position += cur_char.len_utf8();
+                        }
+                        None => break,
                     }
                 }
-                '>' => {
-                    if self.current_position + 1 < self.source.len()
-                        && self.source[self.current_position + 1] as char == '='
-                    {
-                        tokens.push(Token::new(
-                            TokenType::Gte,
-                            String::from(">="),
-                            self.current_line,
-                            self.current_position,
-                            self.current_position + 1,
-                            None,
-                        ))
-                    } else {
-                        tokens.push(Token::new(
-                            TokenType::Gt,
-                            String::from(">"),
-                            self.current_line,
-                            self.current_position,
-                            self.current_position,
-                            None,
-                        ))
-                    }
+
+                if self.current_position >= self.source.len() {
+                    let end = self.source.len();
+                    self.add_error(
+                        LexicalError::new(
+                            ErrorCode::UnterminatedString,
+                            String::from("Non terminated string"),
+                            start_line,
+                            self.span(start_position, start_position + 1),
+                        )
+                        .with_label(self.span(end, end), "the file ends before the closing '\"'"),
+                    );
+                    return;
                 }
-                '/' => {
-                    if self.current_position + 1 < self.source.len()
-                        && self.source[self.current_position + 1] as char == '/'
-                    {
-                        // Comment, consume everything untill the end of the line
-                        while self.source[self.current_position] as char != '\n' {
-                            self.current_position += 1;
-                        }
-                        self.current_line += 1;
-                    } else {
-                        tokens.push(Token::new(
-                            TokenType::Slash,
-                            String::from("/"),
+
+                let value = match decoded {
+                    Some(value) => Cow::Owned(value),
+                    None => Cow::Borrowed(&self.source[start_position + 1..self.current_position]),
+                };
+                // The lexeme keeps the original text, quotes and escapes included
+                self.add_token(Token::new(
+                    TokenType::String,
+                    &self.source[start_position..self.current_position + 1],
+                    self.current_line,
+                    self.span(start_position, self.current_position + 1),
+                    Some(Literal::Str { value }),
+                ));
+            }
+            ' ' => {}
+            '\r' => {}
+            '\t' => {}
+            '\n' => {
+                self.current_line += 1;
+            }
+            _ => {
+                if c.is_ascii_digit() {
+                    let start_position = self.current_position;
+                    match self.scan_number() {
+                        Ok(value) => self.add_token(Token::new(
+                            TokenType::Number,
+                            &self.source[start_position..self.current_position],
                             self.current_line,
-                            self.current_position,
-                            self.current_position,
-                            None,
-                        ))
+                            self.span(start_position, self.current_position),
+                            Some(Literal::Number { value }),
+                        )),
+                        Err(e) => {
+                            self.add_error(e);
+                            // Skip what is left of the malformed literal to report it only once
+                            while self
+                                .char_at(self.current_position)
+                                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                            {
+                                self.current_position += 1;
+                            }
+                        }
                     }
-                }
-                '"' => {
-                    // String literal
+
+                    return;
+                } else if is_identifier_start(c) {
+                    // identifier, consume it to the end
                     let start_position = self.current_position;
-                    let start_line = self.current_line;
-                    self.current_position += 1;
-                    while self.source[self.current_position] as char != '"' {
-                        self.current_position += 1;
-                        if self.source[self.current_position] as char == '\n' {
-                            self.current_line += 1;
-                        }
+                    self.current_position += c.len_utf8();
 
-                        if self.current_position > self.source.len() {
-                            return Err(LexicalError::new(
-                                String::from("Non terminated string starting at line"),
-                                start_line,
-                                start_position,
-                            ));
+                    while let Some(cur_char) = self.char_at(self.current_position) {
+                        if !is_identifier_continue(cur_char) {
+                            break;
                         }
+                        self.current_position += cur_char.len_utf8();
                     }
 
-                    println!(
-                        "start position {}, end position {}",
-                        start_position + 1,
-                        self.current_position - 1
-                    );
-                    let substr = String::from(
-                        str::from_utf8(&self.source[start_position + 1..self.current_position - 1])
-                            .unwrap(),
-                    );
-                    tokens.push(Token::new(
-                        TokenType::String,
-                        substr.clone(),
+                    let substr = &self.source[start_position..self.current_position];
+
+                    match TokenType::get_type_by_reserved_keyword(substr) {
+                        Some(t) => self.add_token(Token::new(
+                            t,
+                            substr,
+                            self.current_line,
+                            self.span(start_position, self.current_position),
+                            None,
+                        )),
+                        None => self.add_token(Token::new(
+                            TokenType::Identifier,
+                            substr,
+                            self.current_line,
+                            self.span(start_position, self.current_position),
+                            Some(Literal::Str {
+                                value: Cow::Borrowed(substr),
+                            }),
+                        )),
+                    };
+
+                    return;
+                } else if let Some((text, token_type)) =
+                    match_punctuator(&self.source[self.current_position..])
+                {
+                    self.add_token(Token::new(
+                        token_type,
+                        text,
                         self.current_line,
-                        self.current_position,
-                        self.current_position,
-                        Some(Literal::Str { value: substr }),
+                        self.span(self.current_position, self.current_position + text.len()),
+                        None,
                     ));
+                    self.current_position += text.len();
+                    return;
+                } else {
+                    self.add_error(LexicalError::new(
+                        ErrorCode::InvalidCharacter,
+                        String::from("Invalid character"),
+                        self.current_line,
+                        self.span(self.current_position, self.current_position + c.len_utf8()),
+                    ));
+                    // Skip the whole character, not just its first byte
+                    self.current_position += c.len_utf8();
+                    return;
                 }
-                ' ' => {}
-                '\r' => {}
-                '\t' => {}
-                '\n' => {
+            }
+        };
+
+        self.current_position += 1;
+    }
+
+    // `///` but not `////`, which is an ordinary comment
+    fn is_doc_comment(&self) -> bool {
+        let rest = &self.source[self.current_position..];
+        rest.starts_with("///") && !rest.starts_with("////")
+    }
+
+    // Called on the first digit. Accepts `0x`, `0b` and `0o` prefixed integers, and decimal
+    // numbers with an optional fraction and exponent, all of them with `_` separators
+    fn scan_number(&mut self) -> Result<f64, LexicalError> {
+        let start_position = self.current_position;
+        let prefixed = match self.char_at(self.current_position + 1) {
+            Some('x' | 'X') => Some((16, "hexadecimal")),
+            Some('b' | 'B') => Some((2, "binary")),
+            Some('o' | 'O') => Some((8, "octal")),
+            _ => None,
+        };
+
+        if let (Some('0'), Some((radix, name))) = (self.char_at(start_position), prefixed) {
+            self.current_position += 2;
+            let digits_start = self.current_position;
+            self.scan_digits(radix, name)?;
+            if self.current_position == digits_start {
+                return Err(LexicalError::new(
+                    ErrorCode::InvalidNumber,
+                    format!(
+                        "Expect {name} digits after '{}'",
+                        &self.source[start_position..digits_start]
+                    ),
+                    self.current_line,
+                    self.span(start_position, digits_start),
+                ));
+            }
+
+            // Digits are already validated
+            let value = self.source[digits_start..self.current_position]
+                .chars()
+                .filter_map(|c| c.to_digit(radix))
+                .fold(0.0, |value, digit| value * radix as f64 + digit as f64);
+            return Ok(value);
+        }
+
+        self.scan_digits(10, "decimal")?;
+        if self.char_at(self.current_position) == Some('.')
+            && self
+                .char_at(self.current_position + 1)
+                .is_some_and(|c| c.is_ascii_digit())
+        {
+            self.current_position += 1;
+            self.scan_digits(10, "decimal")?;
+        }
+        if let Some('e' | 'E') = self.char_at(self.current_position) {
+            let exponent_position = self.current_position;
+            self.current_position += 1;
+            if let Some('+' | '-') = self.char_at(self.current_position) {
+                self.current_position += 1;
+            }
+            if !self
+                .char_at(self.current_position)
+                .is_some_and(|c| c.is_ascii_digit())
+            {
+                return Err(LexicalError::new(
+                    ErrorCode::InvalidNumber,
+                    String::from("Expect digits in the exponent of the number"),
+                    self.current_line,
+                    self.span(exponent_position, self.current_position),
+                ));
+            }
+            self.scan_digits(10, "decimal")?;
+        }
+
+        // Separators are only copied away when there are some
+        let literal = &self.source[start_position..self.current_position];
+        let parsed = if literal.contains('_') {
+            literal.replace('_', "").parse()
+        } else {
+            literal.parse()
+        };
+        parsed.map_err(|_| {
+            LexicalError::new(
+                ErrorCode::InvalidNumber,
+                String::from("Invalid number"),
+                self.current_line,
+                self.span(start_position, self.current_position),
+            )
+        })
+    }
+
+    // Digits of the given radix, where `_` separators are only allowed between two digits.
+    // Letters right after a non decimal number are reported as invalid digits
+    fn scan_digits(&mut self, radix: u32, name: &str) -> Result<(), LexicalError> {
+        let start_position = self.current_position;
+        let mut after_digit = false;
+        while let Some(c) = self.char_at(self.current_position) {
+            if c.is_digit(radix) {
+                after_digit = true;
+            } else if c == '_' && after_digit {
+                after_digit = false;
+            } else if c == '_' {
+                return Err(LexicalError::new(
+                    ErrorCode::InvalidDigitSeparator,
+                    String::from("Digit separator '_' must follow a digit"),
+                    self.current_line,
+                    self.span(self.current_position, self.current_position + 1),
+                ));
+            } else if radix != 10 && c.is_ascii_alphanumeric() {
+                return Err(LexicalError::new(
+                    ErrorCode::InvalidNumber,
+                    format!("Invalid digit '{c}' in {name} number"),
+                    self.current_line,
+                    self.span(self.current_position, self.current_position + 1),
+                ));
+            } else {
+                break;
+            }
+            self.current_position += 1;
+        }
+
+        if self.current_position > start_position && !after_digit {
+            return Err(LexicalError::new(
+                ErrorCode::InvalidDigitSeparator,
+                String::from("Digit separator '_' must be followed by a digit"),
+                self.current_line,
+                self.span(self.current_position - 1, self.current_position),
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Called on the opening `/*`, consumes up to the matching `*/` as comments can be nested
+    fn scan_block_comment(&mut self) -> Result<(), LexicalError> {
+        let start_position = self.current_position;
+        let start_line = self.current_line;
+        let mut depth = 0;
+        while let Some(c) = self.char_at(self.current_position) {
+            let next = self.char_at(self.current_position + 1);
+            if c == '/' && next == Some('*') {
+                depth += 1;
+                self.current_position += 2;
+            } else if c == '*' && next == Some('/') {
+                depth -= 1;
+                self.current_position += 2;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else {
+                if c == '\n' {
                     self.current_line += 1;
                 }
-                _ => {
-                    if (self.source[self.current_position] as char).is_digit(10) {
-                        let mut accept_dot = true;
-                        let start_position = self.current_position;
-                        self.current_position += 1;
-                        loop {
-                            let cur_char = self.source[self.current_position] as char;
-                            if cur_char.is_digit(10) {
-                                self.current_position += 1;
-                            } else if cur_char == '.'
-                                && accept_dot == true
-                                && self.current_position + 1 < self.source.len()
-                                && (self.source[self.current_position + 1] as char).is_digit(10)
-                            {
-                                accept_dot = false;
-                                self.current_position += 1;
-                            } else {
-                                break;
-                            }
-                        }
+                self.current_position += c.len_utf8();
+            }
+        }
 
-                        let substr = String::from(
-                            str::from_utf8(&self.source[start_position..self.current_position])
-                                .unwrap(),
-                        );
+        let end = self.source.len();
+        Err(LexicalError::new(
+            ErrorCode::UnterminatedBlockComment,
+            String::from("Non terminated block comment"),
+            start_line,
+            self.span(start_position, start_position + 2),
+        )
+        .with_label(self.span(end, end), "the file ends before the closing '*/'"))
+    }
 
-                        tokens.push(Token::new(
-                            TokenType::Number,
-                            substr.clone(),
-                            self.current_line,
-                            start_position,
-                            self.current_position - 1,
-                            Some(Literal::Number {
-                                value: substr.parse().unwrap(),
-                            }),
-                        ));
+    // Called on the backslash, consumes the whole escape sequence even when it is invalid
+    fn scan_escape(&mut self) -> Result<char, LexicalError> {
+        let start_position = self.current_position;
+        self.current_position += 1;
+        let escaped = match self.char_at(self.current_position) {
+            Some(c) => c,
+            None => {
+                return Err(LexicalError::new(
+                    ErrorCode::InvalidEscape,
+                    String::from("Unterminated escape sequence"),
+                    self.current_line,
+                    self.span(start_position, self.current_position),
+                ))
+            }
+        };
+        self.current_position += escaped.len_utf8();
 
-                        self.current_position -= 1;
-                    } else if is_alpha(self.source[self.current_position] as char) {
-                        // identifier, consume it to the end
-                        let start_position = self.current_position;
-                        self.current_position += 1;
+        match escaped {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '0' => Ok('\0'),
+            'u' => self.scan_unicode_escape(start_position),
+            _ => {
+                let error = LexicalError::new(
+                    ErrorCode::InvalidEscape,
+                    format!("Unknown escape sequence '\\{}'", escaped.escape_default()),
+                    self.current_line,
+                    self.span(start_position, self.current_position),
+                );
+                if escaped == '\n' {
+                    self.current_line += 1;
+                }
+                Err(error)
+            }
+        }
+    }
 
-                        loop {
-                            let cur_char = self.source[self.current_position] as char;
-                            if is_alphanumeric(cur_char) {
-                                self.current_position += 1;
-                            } else {
-                                break;
-                            }
-                        }
+    // \u{XXXX} with 1 to 6 hex digits, `start_position` is the one of the backslash
+    fn scan_unicode_escape(&mut self, start_position: usize) -> Result<char, LexicalError> {
+        if self.char_at(self.current_position) != Some('{') {
+            return Err(LexicalError::new(
+                ErrorCode::InvalidUnicodeEscape,
+                String::from("Expect '{' after '\\u'"),
+                self.current_line,
+                self.span(start_position, self.current_position),
+            ));
+        }
+        self.current_position += 1;
 
-                        let substr = String::from(
-                            str::from_utf8(&self.source[start_position..self.current_position])
-                                .unwrap(),
-                        );
-
-                        match TokenType::get_type_by_reserved_keyword(&substr) {
-                            Some(t) => tokens.push(Token::new(
-                                t,
-                                substr.clone(),
-                                self.current_line,
-                                start_position,
-                                self.current_position - 1,
-                                None,
-                            )),
-                            None => tokens.push(Token::new(
-                                TokenType::Identifier,
-                                substr.clone(),
-                                self.current_line,
-                                start_position,
-                                self.current_position - 1,
-                                Some(Literal::Str {
-                                    value: substr.parse().unwrap(),
-                                }),
-                            )),
-                        };
-
-                        self.current_position -= 1;
-                    } else {
-                        return Err(LexicalError::new(
-                            String::from("Invalid character"),
-                            self.current_line,
-                            self.current_position,
-                        ));
+        let digits_start = self.current_position;
+        while self
+            .char_at(self.current_position)
+            .is_some_and(|c| c.is_ascii_hexdigit())
+        {
+            self.current_position += 1;
+        }
+        let digits = &self.source[digits_start..self.current_position];
+
+        if self.char_at(self.current_position) != Some('}') {
+            return Err(LexicalError::new(
+                ErrorCode::InvalidUnicodeEscape,
+                String::from("Expect '}' to close the unicode escape"),
+                self.current_line,
+                self.span(start_position, self.current_position),
+            ));
+        }
+        self.current_position += 1;
+
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(LexicalError::new(
+                ErrorCode::InvalidUnicodeEscape,
+                String::from("Unicode escape must have from 1 to 6 hex digits"),
+                self.current_line,
+                self.span(start_position, self.current_position),
+            ));
+        }
+
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| {
+                LexicalError::new(
+                    ErrorCode::InvalidUnicodeEscape,
+                    format!("Invalid unicode code point '{digits}'"),
+                    self.current_line,
+                    self.span(start_position, self.current_position),
+                )
+            })
+    }
+}
+
+// Text of a source read as bytes, with an error for each invalid UTF-8 sequence. Bytes of
+// those sequences are replaced by spaces so that the offsets, and so the spans, still
+// match the bytes
+pub fn decode_source(bytes: &[u8], file_id: FileId) -> (Cow<'_, str>, Vec<LexicalError>) {
+    if let Ok(source) = std::str::from_utf8(bytes) {
+        return (Cow::Borrowed(source), vec![]);
+    }
+
+    let mut source = String::with_capacity(bytes.len());
+    let mut errors = vec![];
+    let mut line = 1;
+    for chunk in bytes.utf8_chunks() {
+        line += chunk.valid().matches('\n').count();
+        source.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            errors.push(LexicalError::new(
+                ErrorCode::InvalidUtf8,
+                String::from("Invalid UTF-8"),
+                line,
+                Span::new(source.len(), source.len() + chunk.invalid().len(), file_id),
+            ));
+            source.extend(chunk.invalid().iter().map(|_| ' '));
+        }
+    }
+
+    (Cow::Owned(source), errors)
+}
+
+// Tokens are scanned lazily, one lexeme at a time. The last item is always an Eof token,
+// even after lexical errors, and the iterator is exhausted after it
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Result<Token<'a>, LexicalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(match item {
+                    Ok(token) if self.keep_trivia => Ok(self.attach_trivia(token)),
+                    item => item,
+                });
+            }
+            if self.finished {
+                return None;
+            }
+            match self.char_at(self.current_position) {
+                Some(c) => {
+                    let start_position = self.current_position;
+                    self.scan_token(c);
+                    // Whatever was consumed without producing a token is trivia
+                    if self.keep_trivia && !self.pending.iter().any(Result::is_ok) {
+                        let trivia = self.trivia(start_position);
+                        push_trivia(&mut self.leading_trivia, trivia, self.source);
                     }
                 }
-            };
+                None => {
+                    self.finished = true;
+                    self.add_token(Token::new(
+                        TokenType::Eof,
+                        "",
+                        self.current_line,
+                        self.span(self.current_position, self.current_position),
+                        None,
+                    ));
+                }
+            }
+        }
+    }
+}
 
-            self.current_position += 1;
+impl<'a> Scanner<'a> {
+    // Called right after the token is scanned, the pending queue is then empty
+    fn attach_trivia(&mut self, mut token: Token<'a>) -> Token<'a> {
+        token.leading_trivia = std::mem::take(&mut self.leading_trivia);
+        while let Some(c) = self.char_at(self.current_position) {
+            let next = self.char_at(self.current_position + 1);
+            let is_trailing = matches!(c, ' ' | '\t' | '\r')
+                || (c == '/' && matches!(next, Some('/' | '*')) && !self.is_doc_comment());
+            if !is_trailing {
+                break;
+            }
+            let start_position = self.current_position;
+            self.scan_token(c);
+            let trivia = self.trivia(start_position);
+            push_trivia(&mut token.trailing_trivia, trivia, self.source);
         }
 
-        Ok(tokens)
+        token
     }
+
+    fn trivia(&self, start_position: usize) -> Trivia<'a> {
+        let text = &self.source[start_position..self.current_position];
+        let kind = if text.starts_with("//") {
+            TriviaKind::LineComment
+        } else if text.starts_with("/*") {
+            TriviaKind::BlockComment
+        } else if text == "\n" {
+            TriviaKind::Newline
+        } else if text.chars().all(|c| matches!(c, ' ' | '\t' | '\r')) {
+            TriviaKind::Whitespace
+        } else {
+            TriviaKind::Skipped
+        };
+
+        Trivia {
+            kind,
+            text: Cow::Borrowed(text),
+            span: self.span(start_position, self.current_position),
+        }
+    }
+}
+
+// Consecutive whitespace is merged into a single trivia
+fn push_trivia<'a>(trivia: &mut Vec<Trivia<'a>>, new: Trivia<'a>, source: &'a str) {
+    if let Some(last) = trivia.last_mut() {
+        if last.kind == TriviaKind::Whitespace
+            && new.kind == TriviaKind::Whitespace
+            && last.span.end == new.span.start
+        {
+            last.span.end = new.span.end;
+            last.text = Cow::Borrowed(&source[last.span.start..last.span.end]);
+            return;
+        }
+    }
+    trivia.push(new);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Scanner;
-    use crate::tokens::TokenType;
+    use super::{decode_source, Scanner};
+    use crate::source_map::{FileId, Span};
+    use crate::test_utils::{random_sources, Random, FRAGMENTS};
+    use crate::tokens::{Literal, TokenType, Trivia, TriviaKind, PUNCTUATORS};
+    use std::borrow::Cow;
 
     #[test]
     fn test_reserved_keyword() {
         let v = String::from("if(var == 12) {\nprint(\"value\")}");
-        let scanner = Scanner::new(v.as_bytes()).unwrap();
+        let scanner = Scanner::new(&v);
         let tokens = scanner.scan().unwrap();
 
         assert_eq!(tokens[0].token_type, TokenType::If);
@@ -335,6 +694,460 @@ mod tests {
         assert_eq!(tokens[9].token_type, TokenType::String);
         assert_eq!(tokens[10].token_type, TokenType::RightParen);
         assert_eq!(tokens[11].token_type, TokenType::RightBrace);
-        assert_eq!(tokens.len(), 12);
+        assert_eq!(tokens[12].token_type, TokenType::Eof);
+        assert_eq!(tokens.len(), 13);
+    }
+
+    #[test]
+    fn test_compound_operators() {
+        // '}' keeps its own lexeme and the second character of '!=', '<=' and '>=' is not
+        // scanned again as '='
+        let v = String::from("1 <= 2 != 3 >= 4 }");
+        let scanner = Scanner::new(&v);
+        let tokens = scanner.scan().unwrap();
+
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Number,
+                TokenType::Lte,
+                TokenType::Number,
+                TokenType::BangEq,
+                TokenType::Number,
+                TokenType::Gte,
+                TokenType::Number,
+                TokenType::RightBrace,
+                TokenType::Eof,
+            ]
+        );
+        assert_eq!(tokens[7].lexeme, "}");
+    }
+
+    #[test]
+    fn test_scan_all_errors() {
+        let v = String::from("var a = @1;\n# print a$;\n");
+        let (tokens, errors) = Scanner::new(&v).scan_all();
+
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Error: Invalid character. Line 1, position 8..9",
+                "Error: Invalid character. Line 2, position 12..13",
+                "Error: Invalid character. Line 2, position 21..22",
+            ]
+        );
+
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Var,
+                TokenType::Identifier,
+                TokenType::Eq,
+                TokenType::Number,
+                TokenType::Semicolon,
+                TokenType::Print,
+                TokenType::Identifier,
+                TokenType::Semicolon,
+                TokenType::Eof,
+            ]
+        );
+
+        let err = Scanner::new(&v).scan().err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error: Invalid character. Line 1, position 8..9"
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let v = String::from("var s = \"ab\";\nx == 12.5\n");
+        let tokens = Scanner::new(&v).scan().unwrap();
+
+        let spans: Vec<(usize, usize)> =
+            tokens.iter().map(|t| (t.span.start, t.span.end)).collect();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 3),
+                (4, 5),
+                (6, 7),
+                (8, 12),
+                (12, 13),
+                (14, 15),
+                (16, 18),
+                (19, 23),
+                (24, 24)
+            ]
+        );
+        assert_eq!(&v[tokens[3].span.start..tokens[3].span.end], "\"ab\"");
+    }
+
+    #[test]
+    fn test_unicode() {
+        let v = String::from("var café = \"héllo 👋\";\nprint ñ_1 € café;\n");
+        let (tokens, errors) = Scanner::new(&v).scan_all();
+
+        assert_eq!(tokens[1].token_type, TokenType::Identifier);
+        assert_eq!(tokens[1].lexeme, "café");
+        assert_eq!(&v[tokens[1].span.start..tokens[1].span.end], "café");
+        assert_eq!(
+            tokens[3].literal,
+            Some(Literal::Str {
+                value: "héllo 👋".into()
+            })
+        );
+        assert_eq!(&v[tokens[3].span.start..tokens[3].span.end], "\"héllo 👋\"");
+        assert_eq!(tokens[6].lexeme, "ñ_1");
+        assert_eq!(tokens[7].lexeme, "café");
+        assert_eq!(&v[tokens[7].span.start..tokens[7].span.end], "café");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(&v[errors[0].span.start..errors[0].span.end], "€");
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let v = String::from(r#"print "a\"b\n\t\r\\\0\u{e9}\u{1F600}";"#);
+        let tokens = Scanner::new(&v).scan().unwrap();
+
+        assert_eq!(tokens[1].token_type, TokenType::String);
+        assert_eq!(tokens[1].lexeme, &v[6..v.len() - 1]);
+        assert_eq!(
+            tokens[1].literal,
+            Some(Literal::Str {
+                value: "a\"b\n\t\r\\\0é😀".into()
+            })
+        );
+        assert_eq!(tokens[2].token_type, TokenType::Semicolon);
+    }
+
+    #[test]
+    fn test_invalid_escape_sequences() {
+        let v = String::from(r#""\q \u{D800} \u{} \u12 \u{1234567}" "unterminated"#);
+        let (tokens, errors) = Scanner::new(&v).scan_all();
+
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Error: Unknown escape sequence '\\q'. Line 1, position 1..3",
+                "Error: Invalid unicode code point 'D800'. Line 1, position 4..12",
+                "Error: Unicode escape must have from 1 to 6 hex digits. Line 1, position 13..17",
+                "Error: Expect '{' after '\\u'. Line 1, position 18..20",
+                "Error: Unicode escape must have from 1 to 6 hex digits. Line 1, position 23..34",
+                "Error: Non terminated string. Line 1, position 36..37",
+            ]
+        );
+        // Every error points at the backslash of its escape sequence
+        for e in &errors[..5] {
+            assert_eq!(&v[e.span.start..e.span.start + 1], "\\");
+        }
+
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![TokenType::String, TokenType::Eof]);
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let v = String::from("a /* one /* two\n*/ still\n comment */ b / c /*/ é */\nd");
+        let (tokens, errors) = Scanner::new(&v).scan_all();
+
+        assert!(errors.is_empty());
+        let lexemes: Vec<(&str, usize)> =
+            tokens.iter().map(|t| (t.lexeme.as_ref(), t.line)).collect();
+        assert_eq!(
+            lexemes,
+            vec![("a", 1), ("b", 3), ("/", 3), ("c", 3), ("d", 4), ("", 4)]
+        );
+
+        let v = String::from("a\n  /* /* */\n");
+        let (tokens, errors) = Scanner::new(&v).scan_all();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec!["Error: Non terminated block comment. Line 2, position 4..6"]
+        );
+        assert_eq!(tokens.last().unwrap().token_type, TokenType::Eof);
+    }
+
+    #[test]
+    fn test_lazy_iterator() {
+        let v = String::from("print a; @ \"b\\q\"");
+        let mut scanner = Scanner::new(&v).peekable();
+
+        assert_eq!(
+            scanner.peek().unwrap().as_ref().unwrap().token_type,
+            TokenType::Print
+        );
+        let types: Vec<Result<TokenType, String>> = scanner
+            .map(|item| item.map(|t| t.token_type).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                Ok(TokenType::Print),
+                Ok(TokenType::Identifier),
+                Ok(TokenType::Semicolon),
+                Err(String::from(
+                    "Error: Invalid character. Line 1, position 9..10"
+                )),
+                Err(String::from(
+                    "Error: Unknown escape sequence '\\q'. Line 1, position 13..15"
+                )),
+                Ok(TokenType::String),
+                Ok(TokenType::Eof),
+            ]
+        );
+
+        // Nothing is produced after the Eof token
+        let mut scanner = Scanner::new(" ");
+        assert_eq!(scanner.next().unwrap().unwrap().token_type, TokenType::Eof);
+        assert!(scanner.next().is_none());
+        assert!(scanner.next().is_none());
+
+        // Even an empty source ends with Eof
+        let tokens = Scanner::new("").scan().unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].token_type, TokenType::Eof);
+        assert_eq!(tokens[0].span, Span::new(0, 0, FileId::default()));
+    }
+
+    #[test]
+    fn test_borrowed_tokens() {
+        let v = String::from("var s = \"plain\" + \"esc\\t\";");
+        let tokens = Scanner::new(&v).scan().unwrap();
+
+        // Only the string with an escape sequence needs its own buffer
+        assert!(tokens.iter().all(|t| matches!(t.lexeme, Cow::Borrowed(_))));
+        assert!(matches!(
+            tokens[3].literal,
+            Some(Literal::Str {
+                value: Cow::Borrowed("plain")
+            })
+        ));
+        assert!(matches!(
+            &tokens[5].literal,
+            Some(Literal::Str { value: Cow::Owned(value) }) if value == "esc\t"
+        ));
+
+        let owned = tokens[3].clone().into_owned();
+        assert!(matches!(owned.lexeme, Cow::Owned(_)));
+        assert_eq!(owned.lexeme, "\"plain\"");
+    }
+
+    #[test]
+    fn test_numbers() {
+        let v = String::from("0xFF 0Xa_b 0b1010 0o17 1_000_000 6.02e23 1E-9 2.5e+3 3.25 7.foo");
+        let tokens = Scanner::new(&v).scan().unwrap();
+
+        let numbers: Vec<f64> = tokens
+            .iter()
+            .filter_map(|t| match t.literal {
+                Some(Literal::Number { value }) => Some(value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            numbers,
+            vec![
+                255.0,
+                171.0,
+                10.0,
+                15.0,
+                1_000_000.0,
+                6.02e23,
+                1e-9,
+                2500.0,
+                3.25,
+                7.0
+            ]
+        );
+        assert_eq!(tokens[1].lexeme, "0Xa_b");
+        assert_eq!(tokens[10].token_type, TokenType::Dot);
+    }
+
+    #[test]
+    fn test_malformed_numbers() {
+        let v = String::from("0x; 1e; 1_; 0b1021; 1__0; 0o_7; 2.5E+x; 0xFG;");
+        let (tokens, errors) = Scanner::new(&v).scan_all();
+
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Error: Expect hexadecimal digits after '0x'. Line 1, position 0..2",
+                "Error: Expect digits in the exponent of the number. Line 1, position 5..6",
+                "Error: Digit separator '_' must be followed by a digit. Line 1, position 9..10",
+                "Error: Invalid digit '2' in binary number. Line 1, position 16..17",
+                "Error: Digit separator '_' must follow a digit. Line 1, position 22..23",
+                "Error: Digit separator '_' must follow a digit. Line 1, position 28..29",
+                "Error: Expect digits in the exponent of the number. Line 1, position 35..37",
+                "Error: Invalid digit 'G' in hexadecimal number. Line 1, position 43..44",
+            ]
+        );
+        // Each malformed literal is skipped as a whole
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(
+            types,
+            [vec![TokenType::Semicolon; 8], vec![TokenType::Eof]].concat()
+        );
+    }
+
+    #[test]
+    fn test_trivia() {
+        let v = String::from("var a = 1; // one\n\n  /* two */ print a;\t\n");
+        let tokens = Scanner::new(&v).with_trivia().scan().unwrap();
+
+        let texts = |trivia: &[Trivia]| -> Vec<(TriviaKind, String)> {
+            trivia
+                .iter()
+                .map(|t| (t.kind, t.text.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            texts(&tokens[4].trailing_trivia),
+            vec![
+                (TriviaKind::Whitespace, String::from(" ")),
+                (TriviaKind::LineComment, String::from("// one")),
+            ]
+        );
+        assert_eq!(
+            texts(&tokens[5].leading_trivia),
+            vec![
+                (TriviaKind::Newline, String::from("\n")),
+                (TriviaKind::Newline, String::from("\n")),
+                (TriviaKind::Whitespace, String::from("  ")),
+                (TriviaKind::BlockComment, String::from("/* two */")),
+                (TriviaKind::Whitespace, String::from(" ")),
+            ]
+        );
+        assert_eq!(tokens[5].line, 3);
+        assert_eq!(
+            texts(&tokens[8].leading_trivia),
+            vec![(TriviaKind::Newline, String::from("\n"))]
+        );
+        assert_eq!(tokens[8].token_type, TokenType::Eof);
+
+        // Without trivia nothing is attached
+        let tokens = Scanner::new(&v).scan().unwrap();
+        assert!(tokens.iter().all(|t| t.leading_trivia.is_empty()));
+        assert!(tokens.iter().all(|t| t.trailing_trivia.is_empty()));
+    }
+
+    #[test]
+    fn test_trivia_is_lossless() {
+        for source in random_sources(5000) {
+            let mut rebuilt = String::new();
+            for item in Scanner::new(&source).with_trivia() {
+                let Ok(token) = item else { continue };
+                for trivia in &token.leading_trivia {
+                    rebuilt.push_str(&trivia.text);
+                }
+                rebuilt.push_str(&token.lexeme);
+                for trivia in &token.trailing_trivia {
+                    rebuilt.push_str(&trivia.text);
+                }
+            }
+            assert_eq!(rebuilt, source);
+        }
+    }
+
+    #[test]
+    fn test_punctuators() {
+        // Each punctuator of the table scans back to itself
+        for (text, token_type) in PUNCTUATORS {
+            let tokens = Scanner::new(text).scan().unwrap();
+            assert_eq!(tokens[0].token_type, *token_type);
+            assert_eq!(tokens[0].lexeme, *text);
+            assert_eq!(token_type.to_string(), *text);
+        }
+
+        // Longest match first
+        let v = String::from("!===<=>=>}{/");
+        let tokens = Scanner::new(&v).scan().unwrap();
+        let lexemes: Vec<&str> = tokens.iter().map(|t| t.lexeme.as_ref()).collect();
+        assert_eq!(
+            lexemes,
+            vec!["!=", "==", "<=", ">=", ">", "}", "{", "/", ""]
+        );
+        assert_eq!(TokenType::Eof.to_string(), "end of file");
+        assert_eq!(TokenType::While.to_string(), "while");
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let bytes = b"var a = \"\xFF\";\nprint \xC3\x28;";
+        let (source, errors) = decode_source(bytes, FileId::default());
+
+        assert_eq!(source.len(), bytes.len());
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Error: Invalid UTF-8. Line 1, position 9..10",
+                "Error: Invalid UTF-8. Line 2, position 19..20",
+            ]
+        );
+        // `\xC3` starts a sequence that `(` cannot continue
+        let tokens = Scanner::new(&source).scan().unwrap();
+        assert_eq!(tokens[6].token_type, TokenType::LeftParen);
+        assert_eq!(tokens[6].span.start, 20);
+
+        assert!(matches!(
+            decode_source("é".as_bytes(), FileId::default()),
+            (Cow::Borrowed("é"), errors) if errors.is_empty()
+        ));
+    }
+
+    // Repeated to build deep nesting, long numbers and long escapes
+    const RUNS: &[&str] = &[
+        "/*", "*/", "(", "{", "\"", "\\", "\\u{", "0", "9", "f", "_", "1_", "0x", "e", ".", "é",
+        "\n", "//",
+    ];
+
+    // LOX_FUZZ_ITERATIONS overrides the number of generated inputs
+    fn fuzz(default_iterations: usize) {
+        let iterations = std::env::var("LOX_FUZZ_ITERATIONS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default_iterations);
+        let mut random = Random(0xD1B5_4A32_D192_ED03);
+        for _ in 0..iterations {
+            // Fragments of Lox mixed with arbitrary bytes and long runs of one construct
+            let mut bytes = vec![];
+            for _ in 0..random.below(64) {
+                match random.below(16) {
+                    0..=3 => bytes.push(random.next() as u8),
+                    4 => {
+                        let run = random.pick(RUNS).repeat(1 + random.below(300));
+                        bytes.extend_from_slice(run.as_bytes());
+                    }
+                    _ => bytes.extend_from_slice(random.pick(FRAGMENTS).as_bytes()),
+                }
+            }
+
+            let result = std::panic::catch_unwind(|| {
+                let (source, _) = decode_source(&bytes, FileId::default());
+                Scanner::new(&source).scan_all();
+                Scanner::new(&source).with_trivia().for_each(drop);
+            });
+            assert!(result.is_ok(), "Scanner panicked on {bytes:?}");
+        }
+    }
+
+    #[test]
+    fn test_never_panics() {
+        fuzz(20_000);
+    }
+
+    // Run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_never_panics_on_millions_of_inputs() {
+        fuzz(5_000_000);
     }
 }
@@ -1,120 +1,264 @@
-pub enum Literal {
-    Str { value: String },
+use crate::source_map::Span;
+use std::borrow::Cow;
+use std::fmt;
+
+// Values borrow from the source unless they had to be decoded, like strings with escapes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal<'src> {
+    Str { value: Cow<'src, str> },
     Number { value: f64 },
+    Bool { value: bool },
+    Nil,
 }
 
-impl Literal {
+impl Literal<'_> {
+    // Detach it from the source it was scanned from
+    pub fn into_owned(self) -> Literal<'static> {
+        match self {
+            Literal::Str { value } => Literal::Str {
+                value: Cow::Owned(value.into_owned()),
+            },
+            Literal::Number { value } => Literal::Number { value },
+            Literal::Bool { value } => Literal::Bool { value },
+            Literal::Nil => Literal::Nil,
+        }
+    }
+
     pub fn to_str(&self) -> String {
         match self {
             Literal::Number { value } => {
                 format!("{value}")
             }
-            Literal::Str { value } => {
+            Literal::Str { value } => value.to_string(),
+            Literal::Bool { value } => {
                 format!("{value}")
             }
+            Literal::Nil => String::from("nil"),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum TokenType {
-    // Single character token
-    LeftParen,
-    RightParen,
-    LeftBrace,
-    RightBrace,
-    Comma,
-    Dot,
-    Minus,
-    Plus,
-    Semicolon,
-    Slash,
-    Star,
-
-    Bang,
-    BangEq,
-    Eq,
-    EqEq,
-    Gt,
-    Gte,
-    Lt,
-    Lte,
-
-    // literals
-    Identifier,
-    String,
-    Number,
-
-    // keywords
-    And,
-    Class,
-    Else,
-    False,
-    Fun,
-    For,
-    If,
-    Nil,
-    Or,
-    Print,
-    Return,
-    Super,
-    This,
-    True,
-    Var,
-    While,
-
-    Eof,
+// Single declarative spec of every token type. Punctuators and keywords come with their
+// text, which drives the scanner, and the other token types with their description. Any
+// new operator only needs a line here, the scanner always takes the longest punctuator
+macro_rules! token_types {
+    (
+        punctuators { $($punctuator:ident => $punctuator_text:literal,)* }
+        keywords { $($keyword:ident => $keyword_text:literal,)* }
+        others { $($other:ident => $description:literal,)* }
+    ) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum TokenType {
+            $($punctuator,)*
+            $($keyword,)*
+            $($other,)*
+        }
+
+        pub const PUNCTUATORS: &[(&str, TokenType)] = &[
+            $(($punctuator_text, TokenType::$punctuator),)*
+        ];
+
+        pub const KEYWORDS: &[(&str, TokenType)] = &[
+            $(($keyword_text, TokenType::$keyword),)*
+        ];
+
+        const MAX_PUNCTUATOR_LEN: usize = {
+            let mut max = 0;
+            $(
+                if $punctuator_text.len() > max {
+                    max = $punctuator_text.len();
+                }
+            )*
+            max
+        };
+
+        impl TokenType {
+            pub fn get_type_by_punctuator(p: &str) -> Option<TokenType> {
+                match p {
+                    $($punctuator_text => Some(TokenType::$punctuator),)*
+                    _ => None,
+                }
+            }
+
+            pub fn get_type_by_reserved_keyword(k: &str) -> Option<TokenType> {
+                match k {
+                    $($keyword_text => Some(TokenType::$keyword),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl fmt::Display for TokenType {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let text = match self {
+                    $(TokenType::$punctuator => $punctuator_text,)*
+                    $(TokenType::$keyword => $keyword_text,)*
+                    $(TokenType::$other => $description,)*
+                };
+                write!(f, "{text}")
+            }
+        }
+    };
 }
 
-impl TokenType {
-    pub fn get_type_by_reserved_keyword(k: &str) -> Option<TokenType> {
-        match k {
-            "and" => Some(TokenType::And),
-            "class" => Some(TokenType::Class),
-            "else" => Some(TokenType::Else),
-            "false" => Some(TokenType::False),
-            "for" => Some(TokenType::For),
-            "fun" => Some(TokenType::Fun),
-            "if" => Some(TokenType::If),
-            "nil" => Some(TokenType::Nil),
-            "or" => Some(TokenType::Or),
-            "print" => Some(TokenType::Print),
-            "return" => Some(TokenType::Return),
-            "super" => Some(TokenType::Super),
-            "this" => Some(TokenType::This),
-            "true" => Some(TokenType::True),
-            "var" => Some(TokenType::Var),
-            "while" => Some(TokenType::While),
-            _ => None,
+token_types! {
+    punctuators {
+        LeftParen => "(",
+        RightParen => ")",
+        LeftBrace => "{",
+        RightBrace => "}",
+        Comma => ",",
+        Dot => ".",
+        Minus => "-",
+        Plus => "+",
+        Semicolon => ";",
+        Slash => "/",
+        Star => "*",
+        Bang => "!",
+        BangEq => "!=",
+        Eq => "=",
+        EqEq => "==",
+        Gt => ">",
+        Gte => ">=",
+        Lt => "<",
+        Lte => "<=",
+    }
+    keywords {
+        And => "and",
+        Class => "class",
+        Else => "else",
+        False => "false",
+        Fun => "fun",
+        For => "for",
+        If => "if",
+        Nil => "nil",
+        Or => "or",
+        Print => "print",
+        Return => "return",
+        Super => "super",
+        This => "this",
+        True => "true",
+        Var => "var",
+        While => "while",
+    }
+    others {
+        Identifier => "identifier",
+        String => "string",
+        Number => "number",
+        DocComment => "doc comment",
+        Eof => "end of file",
+    }
+}
+
+// Longest punctuator the text starts with
+pub fn match_punctuator(text: &str) -> Option<(&str, TokenType)> {
+    (1..=MAX_PUNCTUATOR_LEN).rev().find_map(|len| {
+        let prefix = text.get(..len)?;
+        TokenType::get_type_by_punctuator(prefix).map(|token_type| (prefix, token_type))
+    })
+}
+
+// Keywords that can start a declaration or a statement, suggested for misspellings
+const STATEMENT_KEYWORDS: &[TokenType] = &[
+    TokenType::Class,
+    TokenType::Fun,
+    TokenType::Var,
+    TokenType::For,
+    TokenType::If,
+    TokenType::Print,
+    TokenType::Return,
+    TokenType::While,
+];
+
+pub fn statement_keywords<'a>() -> impl Iterator<Item = &'a str> {
+    KEYWORDS
+        .iter()
+        .filter(|(_, token_type)| STATEMENT_KEYWORDS.contains(token_type))
+        .map(|(keyword, _)| *keyword)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    // Spaces, tabs and carriage returns
+    Whitespace,
+    Newline,
+    LineComment,
+    BlockComment,
+    // Text dropped because of a lexical error, kept so that no byte of the source is lost
+    Skipped,
+}
+
+// Source text that is not part of any token, only kept when scanning with trivia
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: Cow<'src, str>,
+    pub span: Span,
+}
+
+impl Trivia<'_> {
+    pub fn into_owned(self) -> Trivia<'static> {
+        Trivia {
+            kind: self.kind,
+            text: Cow::Owned(self.text.into_owned()),
+            span: self.span,
         }
     }
 }
 
-pub struct Token {
+// Scanned tokens borrow their lexeme from the source, so scanning allocates nothing per
+// token. The AST keeps `Token<'static>`, see `into_owned`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<'src> {
     pub token_type: TokenType,
-    pub lexeme: String,
+    pub lexeme: Cow<'src, str>,
     pub line: usize,
-    pub position_start: usize,
-    pub position_end: usize,
-    pub literal: Option<Literal>,
+    pub span: Span,
+    pub literal: Option<Literal<'src>>,
+    // Trivia between the previous token and this one, including the line break that ends
+    // the previous line
+    pub leading_trivia: Vec<Trivia<'src>>,
+    // Trivia after this token up to the end of its line, the line break excluded
+    pub trailing_trivia: Vec<Trivia<'src>>,
 }
 
-impl Token {
+impl<'src> Token<'src> {
     pub fn new(
         token_type: TokenType,
-        lexeme: String,
+        lexeme: &'src str,
         line: usize,
-        position_start: usize,
-        position_end: usize,
-        literal: Option<Literal>,
+        span: Span,
+        literal: Option<Literal<'src>>,
     ) -> Self {
         Token {
             token_type,
-            lexeme,
+            lexeme: Cow::Borrowed(lexeme),
             line,
-            position_start,
-            position_end,
+            span,
             literal,
+            leading_trivia: vec![],
+            trailing_trivia: vec![],
+        }
+    }
+
+    // Detach it from the source it was scanned from
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            token_type: self.token_type,
+            lexeme: Cow::Owned(self.lexeme.into_owned()),
+            line: self.line,
+            span: self.span,
+            literal: self.literal.map(Literal::into_owned),
+            leading_trivia: self
+                .leading_trivia
+                .into_iter()
+                .map(Trivia::into_owned)
+                .collect(),
+            trailing_trivia: self
+                .trailing_trivia
+                .into_iter()
+                .map(Trivia::into_owned)
+                .collect(),
         }
     }
 }
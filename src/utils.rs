@@ -1,7 +1,92 @@
-pub fn is_alpha(c: char) -> bool {
-    (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_'
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+// Identifiers follow the Unicode XID rules, with `_` also allowed as first character
+pub fn is_identifier_start(c: char) -> bool {
+    is_xid_start(c) || c == '_'
+}
+
+pub fn is_identifier_continue(c: char) -> bool {
+    is_xid_continue(c)
+}
+
+// Number of single character insertions, deletions, substitutions and swaps of adjacent
+// characters needed to turn one string into the other
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Three rows of the distance matrix are enough: swaps look two characters back
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + substitution);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
 }
 
-pub fn is_alphanumeric(c: char) -> bool {
-    is_alpha(c) || c.is_digit(10)
+// Closest candidate to a misspelled name, if it is close enough to be a likely typo: one
+// edit for every three characters. Ties go to the alphabetically first candidate so that
+// suggestions do not depend on hash map order
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = name.chars().count().max(3) / 3;
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+// Help pointing at the closest candidate to a misspelled name, if any is close
+pub fn suggestion<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    closest_match(name, candidates).map(|similar| format!("did you mean '{similar}'?"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest_match, edit_distance, suggestion};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("fun", ""), 3);
+        assert_eq!(edit_distance("fucn", "fun"), 1);
+        assert_eq!(edit_distance("retrun", "return"), 1);
+        assert_eq!(edit_distance("whlie", "while"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let keywords = ["for", "fun", "print", "return", "while"];
+        assert_eq!(closest_match("fucn", keywords), Some("fun"));
+        assert_eq!(closest_match("retrun", keywords), Some("return"));
+        assert_eq!(closest_match("whlie", keywords), Some("while"));
+        // `fur` is one edit away from both `for` and `fun`
+        assert_eq!(closest_match("fur", keywords), Some("for"));
+        assert_eq!(closest_match("count", keywords), None);
+        assert_eq!(closest_match("print", keywords), None);
+        assert_eq!(
+            suggestion("retrun", keywords).as_deref(),
+            Some("did you mean 'return'?")
+        );
+        assert_eq!(suggestion("count", keywords), None);
+    }
 }